@@ -0,0 +1,52 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use minswpr::board::{Board, CellFlags};
+
+/// Counts the mines around a cell the way it was done before the counts were
+/// cached, by collecting the neighbors of the cell
+fn count_uncached(board: &Board, x: u32, y: u32) -> usize {
+    board
+        .neighbors(x, y)
+        .iter()
+        .filter(|p| board.cell(p.x, p.y).contains(CellFlags::MINE))
+        .count()
+}
+
+fn make_board(size: usize) -> Board {
+    let mut board = Board::new_deferred(size, size, size * size / 5, 0).unwrap();
+    board.generate(0, 0, true, false);
+    board
+}
+
+/// Queries the adjacent mines of every cell on the board, like drawing a fully
+/// revealed board does every frame
+fn adjacent_mines(c: &mut Criterion) {
+    let mut group = c.benchmark_group("adjacent_mines");
+
+    for size in [30, 100, 300].iter() {
+        let board = make_board(*size);
+        let (w, h) = (board.width() as u32, board.height() as u32);
+
+        group.bench_with_input(BenchmarkId::new("uncached", size), &board, |b, board| {
+            b.iter(|| {
+                (0..h)
+                    .flat_map(|y| (0..w).map(move |x| (x, y)))
+                    .map(|(x, y)| count_uncached(board, x, y))
+                    .sum::<usize>()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("cached", size), &board, |b, board| {
+            b.iter(|| {
+                (0..h)
+                    .flat_map(|y| (0..w).map(move |x| (x, y)))
+                    .map(|(x, y)| board.count_adjacent_mines(x, y))
+                    .sum::<usize>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, adjacent_mines);
+criterion_main!(benches);
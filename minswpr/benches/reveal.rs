@@ -0,0 +1,36 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use minswpr::board::Board;
+
+/// Opens empty square boards of increasing size in one click
+fn reveal_empty(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reveal_empty");
+    group.sample_size(10);
+
+    for size in [100, 300, 1000].iter() {
+        let board = Board::new(*size, *size, 0).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter(|| board.clone().reveal_from(0, 0))
+        });
+    }
+
+    group.finish();
+}
+
+/// Opens boards with a beginner density of mines from the first click
+fn reveal_sparse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reveal_sparse");
+    group.sample_size(10);
+
+    for size in [100, 300, 1000].iter() {
+        let mut board = Board::new_deferred(*size, *size, size * size / 100, 0).unwrap();
+        board.generate(0, 0, true, false);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter(|| board.clone().reveal_from(0, 0))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, reveal_empty, reveal_sparse);
+criterion_main!(benches);
@@ -1,11 +1,19 @@
-use crate::board::Board;
 use crate::config::Config;
-use crate::control::{Button, Stopwatch};
+use crate::control::{Button, Cursor, MouseChord, NameEntry};
+use crate::draw::board::heatmap::Heatmap;
+use crate::game::Game;
 use crate::layout::Layout;
 use crate::math::Point;
-use crate::{GameState, ModelRef};
+use crate::record::GameRecord;
+use crate::replay::{self, Input, Playback, Recorder};
+use crate::save;
+use crate::scores::{self, HighScores};
+use crate::stats::{self, Stats};
+use crate::{GameState, ModelRef, MsResult};
+use std::cell::Cell;
 use std::cmp;
 use std::collections::HashMap;
+use std::time::Duration;
 
 type ButtonMap = HashMap<&'static str, ModelRef<Button>>;
 
@@ -17,8 +25,29 @@ pub struct Context {
     game_state: GameState,
     #[builder(default)]
     layout: Layout,
-    board: ModelRef<Board>,
-    stopwatch: ModelRef<Stopwatch>,
+    game: ModelRef<Game>,
+    heatmap: ModelRef<Heatmap>,
+    cursor: ModelRef<Cursor>,
+    mouse_chord: ModelRef<MouseChord>,
+    recorder: ModelRef<Recorder>,
+    #[builder(default)]
+    playback: Option<Playback>,
+    #[builder(default)]
+    record: Option<GameRecord>,
+    high_scores: HighScores,
+    #[builder(default)]
+    name_entry: Option<NameEntry>,
+    stats: Stats,
+    #[builder(default)]
+    is_counted: bool,
+    #[builder(default)]
+    stats_visible: Cell<bool>,
+    #[builder(default)]
+    result_visible: Cell<bool>,
+    #[builder(default)]
+    guess_required: Cell<bool>,
+    #[builder(default)]
+    is_zoomed: Cell<bool>,
     #[builder(default)]
     buttons: ButtonMap,
 }
@@ -54,14 +83,203 @@ impl Context {
         self.layout = layout
     }
 
-    /// Returns a `RefCell` of the `Board`
-    pub fn board(&self) -> &ModelRef<Board> {
-        &self.board
+    /// Returns a `RefCell` of the `Game` being played
+    pub fn game(&self) -> &ModelRef<Game> {
+        &self.game
+    }
+
+    /// Returns a `RefCell` of the mine probability `Heatmap`
+    pub fn heatmap(&self) -> &ModelRef<Heatmap> {
+        &self.heatmap
+    }
+
+    /// Returns a `RefCell` of the keyboard `Cursor` on the board
+    pub fn cursor(&self) -> &ModelRef<Cursor> {
+        &self.cursor
+    }
+
+    /// Returns a `RefCell` of the `MouseChord` tracking the mouse buttons held
+    /// over the board
+    pub fn mouse_chord(&self) -> &ModelRef<MouseChord> {
+        &self.mouse_chord
+    }
+
+    /// Returns a `RefCell` of the `Recorder` recording the inputs of the
+    /// current game
+    pub fn recorder(&self) -> &ModelRef<Recorder> {
+        &self.recorder
+    }
+
+    /// Returns `Some(&Playback)` if a replay is being played instead of a game,
+    /// `None` otherwise
+    pub fn playback(&self) -> Option<&Playback> {
+        self.playback.as_ref()
+    }
+
+    /// Returns `Some(&mut Playback)` if a replay is being played instead of a
+    /// game, `None` otherwise
+    pub fn playback_mut(&mut self) -> Option<&mut Playback> {
+        self.playback.as_mut()
+    }
+
+    /// Plays the inputs of the `Playback` that are due after the specified
+    /// real time `dt` has passed. This method has no effect if no replay is
+    /// being played.
+    pub fn advance_playback(&mut self, dt: Duration) {
+        let inputs = match &mut self.playback {
+            Some(p) => p.advance(dt, &mut self.game.borrow_mut()),
+            None => return,
+        };
+
+        for input in inputs {
+            self.follow_input(input);
+        }
+        self.game_state = self.game.borrow().state();
+    }
+
+    /// Plays the next input of the `Playback` right away. This method has no
+    /// effect if no replay is being played or it is finished.
+    pub fn step_playback(&mut self) {
+        let input = match &mut self.playback {
+            Some(p) => p.step(&mut self.game.borrow_mut()),
+            None => return,
+        };
+
+        if let Some(input) = input {
+            self.follow_input(input);
+        }
+        self.game_state = self.game.borrow().state();
+    }
+
+    /// Moves the keyboard cursor like it moved when the specified `input` was
+    /// recorded
+    fn follow_input(&self, input: Input) {
+        let mut cursor = self.cursor.borrow_mut();
+        match input {
+            Input::Cursor(x, y) => cursor.set_pos(Some(point!(x, y))),
+            Input::Reveal(x, y) | Input::Chord(x, y) | Input::Flag(x, y)
+                if cursor.pos().is_some() =>
+            {
+                cursor.set_pos(Some(point!(x, y)))
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `Some(&GameRecord)` with the result of the current game if it
+    /// is over and has been recorded, `None` otherwise
+    pub fn record(&self) -> Option<&GameRecord> {
+        self.record.as_ref()
+    }
+
+    /// Sets the result of the current game
+    pub fn set_record(&mut self, record: Option<GameRecord>) {
+        self.record = record
+    }
+
+    /// Returns the `HighScores` read from the high score file on launch
+    pub fn high_scores(&self) -> &HighScores {
+        &self.high_scores
+    }
+
+    /// Returns `Some(&NameEntry)` if the player is typing a name for a new high
+    /// score, `None` otherwise
+    pub fn name_entry(&self) -> Option<&NameEntry> {
+        self.name_entry.as_ref()
+    }
+
+    /// Returns `Some(&mut NameEntry)` if the player is typing a name for a new
+    /// high score, `None` otherwise
+    pub fn name_entry_mut(&mut self) -> Option<&mut NameEntry> {
+        self.name_entry.as_mut()
+    }
+
+    /// Sets the name being typed for a new high score
+    pub fn set_name_entry(&mut self, name_entry: Option<NameEntry>) {
+        self.name_entry = name_entry
+    }
+
+    /// Adds the score of the current `NameEntry` to the `HighScores` and writes
+    /// them to the high score file. Scores entered without a name are saved as
+    /// `"anonymous"`
+    pub fn submit_name_entry(&mut self) -> MsResult {
+        let entry = match self.name_entry.take() {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+
+        let name = match entry.name().trim() {
+            "" => "anonymous",
+            name => name,
+        };
+
+        self.high_scores.insert(entry.record(), name);
+        scores::write(&self.high_scores, &scores::path()?)
+    }
+
+    /// Returns the lifetime `Stats` of every board
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Counts the specified `record` toward the lifetime `Stats` and writes
+    /// them to the statistics file. Only the first result of a game is
+    /// counted, so that undoing a loss does not count the game twice
+    pub fn count_game(&mut self, record: &GameRecord) -> MsResult {
+        if self.is_counted {
+            return Ok(());
+        }
+
+        self.is_counted = true;
+        self.stats.add(record);
+        stats::write(&self.stats, &stats::path()?)
+    }
+
+    /// Marks the current game as not yet counted toward the `Stats`, for when
+    /// a new game starts
+    pub fn reset_counted(&mut self) {
+        self.is_counted = false
+    }
+
+    /// Returns true if the statistics screen is shown over the board
+    pub fn is_stats_visible(&self) -> bool {
+        self.stats_visible.get()
     }
 
-    /// Returns a `RefCell` of the `Stopwatch`
-    pub fn stopwatch(&self) -> &ModelRef<Stopwatch> {
-        &self.stopwatch
+    /// Shows or hides the statistics screen
+    pub fn set_stats_visible(&self, stats_visible: bool) {
+        self.stats_visible.set(stats_visible)
+    }
+
+    /// Returns true if the result of the finished game is shown over the board
+    pub fn is_result_visible(&self) -> bool {
+        self.result_visible.get()
+    }
+
+    /// Shows or hides the result of the finished game
+    pub fn set_result_visible(&self, result_visible: bool) {
+        self.result_visible.set(result_visible)
+    }
+
+    /// Returns true if no board that can be cleared without guessing was
+    /// found for the current game, see `game::Event::GuessRequired`
+    pub fn is_guess_required(&self) -> bool {
+        self.guess_required.get()
+    }
+
+    /// Sets whether the current game may need guessing
+    pub fn set_guess_required(&self, guess_required: bool) {
+        self.guess_required.set(guess_required)
+    }
+
+    /// Returns true if the window is drawn at twice its size
+    pub fn is_zoomed(&self) -> bool {
+        self.is_zoomed.get()
+    }
+
+    /// Doubles the size of the window, or restores it if it is already doubled
+    pub fn toggle_zoom(&self) {
+        self.is_zoomed.set(!self.is_zoomed.get())
     }
 
     /// Returns a `Vec<&Model<Button>>` of all the buttons being maintained
@@ -83,6 +301,29 @@ impl Context {
         self.buttons.insert(id, ModelRef::new(button));
     }
 
+    /// Writes the current game to the save file so that it can be resumed on
+    /// the next launch. Games that have not started or are over are not saved,
+    /// and remove the save file instead
+    pub fn save_game(&self) -> MsResult {
+        let path = save::path()?;
+        match self.game.borrow().save() {
+            Some(save) => save::write(&save, &path),
+            None => save::remove(&path),
+        }
+    }
+
+    /// Writes the replay of the current game to the replays directory if it is
+    /// over and its inputs were recorded
+    pub fn write_replay(&self) -> MsResult {
+        let game = self.game.borrow();
+        let recorder = self.recorder.borrow();
+
+        match recorder.replay(&game) {
+            Some(r) => replay::write(&r, &replay::dir()?.join(recorder.file_name(&game))),
+            None => Ok(()),
+        }
+    }
+
     /// Return `Some(Point<u32>)` with the board position of the cell that
     /// occupies the point on the screen specified. Returns `None` otherwise.
     ///
@@ -94,7 +335,8 @@ impl Context {
         let cell_config = &self.config.board.cells;
         let cell_dimen = &cell_config.dimen.as_i32();
         let border_width = cell_config.border_width as i32;
-        let board = self.board.borrow();
+        let game = self.game.borrow();
+        let board = game.board();
         let screen_pos = point!(x, y);
         let board_pos = self.layout.get("board").unwrap().pos();
 
@@ -5,18 +5,30 @@ pub use self::context::*;
 use self::{Context, ContextBuilder};
 use super::ModelRef;
 use crate::board::Board;
-use crate::config::{self, Config};
-use crate::control::{Button, Stopwatch};
+use crate::config::{self, Config, OverlayConfig};
+use crate::control::{Button, Cursor, MouseChord, NameEntry};
+use crate::draw::board::heatmap::Heatmap;
 use crate::draw::board::DrawBoard;
+use crate::draw::overlay;
 use crate::draw::{CanvasRef, Draw, DrawContext, DrawRect};
 use crate::fonts::Fonts;
+use crate::game::{Game, GameState};
 use crate::input;
 use crate::input::events;
 use crate::layout::control::ControlLayoutBuilder;
 use crate::layout::{Element, ElementBuilder, Layout, LayoutBuilder};
 use crate::math::{Dimen, Point};
+use crate::record::GameRecord;
+use crate::replay::{Playback, Recorder, Replay};
+use crate::save::{self, SaveGame};
+use crate::scores::{self, HighScores};
+use crate::stats::{self, Stats};
 use crate::MsResult;
+use sdl2::messagebox::{
+    self, ButtonData, ClickedButton, MessageBoxButtonFlag, MessageBoxColorScheme, MessageBoxFlag,
+};
 use sdl2::ttf::Sdl2TtfContext;
+use sdl2::video::Window;
 use sdl2::{self, EventPump, VideoSubsystem};
 use std::cell::RefCell;
 use std::convert::TryInto;
@@ -24,7 +36,10 @@ use std::path::Path;
 use std::process;
 use std::rc::Rc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Shown in the window title when `game::Event::GuessRequired` is received
+const GUESS_WARNING: &str = "no board without guessing was found, this one may need a guess";
 
 /// The application root
 pub struct Minswpr {
@@ -50,11 +65,51 @@ impl Minswpr {
     /// Starts the game. Returns an `Err` if an error occurs during
     /// initialization or the main game loop.
     pub fn start(&mut self) -> MsResult {
+        let (game, is_resumed) = match self.load_save() {
+            Some(save) => {
+                // keep playing at the difficulty of the saved game
+                let b = &save.board;
+                self.config.board.dimen = point!(b.width(), b.height());
+                self.config.board.num_mines = b.num_mines();
+
+                let bc = &self.config.board;
+                let mut game = Game::from_save(save, bc.safe_area, bc.no_guess);
+                game.board_mut().set_question_marks(bc.question_marks);
+                (game, true)
+            }
+            None => (self.make_game()?, false),
+        };
+
+        // the inputs before the game was saved are not known
+        let recorder = Recorder::new(self.config.replay.record && !is_resumed);
+
+        self.run(game, recorder, None)
+    }
+
+    /// Plays back the specified `replay` instead of a game. The board does not
+    /// take input during playback. Returns an `Err` if the replay does not
+    /// describe a valid board or an error occurs in the main game loop.
+    pub fn start_replay(&mut self, replay: Replay) -> MsResult {
+        self.config.board.dimen = point!(replay.width, replay.height);
+        self.config.board.num_mines = replay.num_mines;
+
+        let playback = Playback::new(replay);
+        let game = playback.make_game()?;
+        self.run(game, Recorder::new(false), Some(playback))
+    }
+
+    fn run(&mut self, game: Game, recorder: Recorder, playback: Option<Playback>) -> MsResult {
         let mut ctx = ContextBuilder::default()
             .config(self.config.clone())
-            .game_state(GameState::Ready)
-            .board(ModelRef::new(self.make_board()?))
-            .stopwatch(ModelRef::new(Stopwatch::new()))
+            .game_state(game.state())
+            .game(ModelRef::new(game))
+            .heatmap(ModelRef::new(Heatmap::new()))
+            .cursor(ModelRef::new(Cursor::new()))
+            .mouse_chord(ModelRef::new(MouseChord::new()))
+            .recorder(ModelRef::new(recorder))
+            .playback(playback)
+            .high_scores(Self::load_high_scores())
+            .stats(Self::load_stats())
             .build()?;
 
         ctx.insert_button("reset", Button::new());
@@ -80,12 +135,38 @@ impl Minswpr {
         }
 
         let mut last_game_state = GameState::Unknown; // debug
+        let mut last_seed = None;
+        let mut last_status = None;
+        let mut last_zoom = false;
+        let mut last_frame = Instant::now();
 
         loop {
+            let now = Instant::now();
+            ctx.advance_playback(now - last_frame);
+            last_frame = now;
+
             events::backend::poll_events(&mut ctx, &mut self.event_pump);
 
             self.handle_game_state(&mut ctx)?;
 
+            // show the seed of every new board so it can be shared
+            let seed = ctx.game().borrow().board().seed();
+            let status = match ctx.playback() {
+                Some(p) => Some(p.to_string()),
+                None if ctx.is_guess_required() => Some(GUESS_WARNING.to_string()),
+                None => None,
+            };
+            if last_seed != Some(seed) || status != last_status {
+                self.set_title(&draw, seed, status.as_deref())?;
+                last_seed = Some(seed);
+                last_status = status;
+            }
+
+            if ctx.is_zoomed() != last_zoom {
+                last_zoom = ctx.is_zoomed();
+                self.set_zoom(&draw, ctx.layout().dimen(), last_zoom)?;
+            }
+
             draw.with_canvas(|mut c| {
                 c.set_draw_color(self.config.window.bg_color);
                 c.clear();
@@ -94,6 +175,16 @@ impl Minswpr {
             draw.set_game_state(ctx.game_state());
             ctx.layout_mut().draw(&draw, *LAYOUT_POS)?;
 
+            if let Some(entry) = ctx.name_entry() {
+                self.draw_name_entry(&draw, ctx.layout().dimen(), entry)?;
+            } else if ctx.game_state() == GameState::Paused {
+                self.draw_paused(&draw, ctx.layout().dimen())?;
+            } else if ctx.is_stats_visible() {
+                self.draw_stats(&draw, &ctx)?;
+            } else if let Some(record) = ctx.record().filter(|_| ctx.is_result_visible()) {
+                self.draw_result(&draw, ctx.layout().dimen(), record)?;
+            }
+
             draw.canvas().present();
 
             // debug
@@ -109,38 +200,309 @@ impl Minswpr {
     }
 
     fn handle_game_state(&self, ctx: &mut Context) -> MsResult {
-        ctx.set_game_state(match ctx.game_state() {
+        let game_state = match ctx.game_state() {
             GameState::Quit => {
+                if ctx.playback().is_none() {
+                    if let Err(e) = ctx.save_game() {
+                        eprintln!("could not save game: {}", e);
+                    }
+                }
                 process::exit(0);
             }
             GameState::Reset => {
-                // reset the board and the stopwatch
-                let bc = &self.config.board;
-                let bd = &bc.dimen;
-                ctx.board()
+                ctx.game().as_ref().replace(self.make_game()?);
+                ctx.recorder()
                     .as_ref()
-                    .replace(Board::new(bd.width(), bd.height(), bc.num_mines)?);
-                ctx.stopwatch().borrow_mut().reset();
+                    .replace(Recorder::new(self.config.replay.record));
+                ctx.set_record(None);
+                ctx.set_name_entry(None);
+                ctx.set_result_visible(false);
+                ctx.set_guess_required(false);
+                ctx.reset_counted();
                 GameState::Ready
             }
-            GameState::Start => {
-                ctx.stopwatch().borrow_mut().start();
-                GameState::Started
-            }
             GameState::Over(s) => {
-                ctx.stopwatch().borrow_mut().stop();
+                if ctx.record().is_none() {
+                    let record = ctx.game().borrow().record();
+
+                    // the end of a replay shows its result as well
+                    ctx.set_result_visible(record.is_some());
+
+                    // a replay does not count as playing the game again
+                    if let Some(r) = record.as_ref().filter(|_| ctx.playback().is_none()) {
+                        if let Err(e) = ctx.count_game(r) {
+                            eprintln!("could not save statistics: {}", e);
+                        }
+
+                        if let Err(e) = ctx.write_replay() {
+                            eprintln!("could not save replay: {}", e);
+                        }
+
+                        // ask for a name if the win made it into the high scores,
+                        // which shows the result along with it
+                        if let Some(rank) = ctx.high_scores().rank(r) {
+                            ctx.set_name_entry(Some(NameEntry::new(r.clone(), rank)));
+                            ctx.set_result_visible(false);
+                        }
+                    }
+
+                    ctx.set_record(record);
+                }
                 GameState::Over(s)
             }
-            _ => ctx.game_state(),
-        });
+            game_state => {
+                // an action that ended the game may have been undone
+                ctx.set_record(None);
+                ctx.set_name_entry(None);
+                ctx.set_result_visible(false);
+                game_state
+            }
+        };
 
+        ctx.set_game_state(game_state);
         Ok(())
     }
 
-    fn make_board(&self) -> MsResult<Board> {
+    /// Returns the game in the save file if there is one and the player chooses
+    /// to resume it. The save file is removed either way, so that it is only
+    /// offered once.
+    fn load_save(&self) -> Option<SaveGame> {
+        let path = match save::path() {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("could not find save file: {}", e);
+                return None;
+            }
+        };
+
+        let save = save::read(&path).unwrap_or_else(|e| {
+            eprintln!("could not read save file: {}", e);
+            None
+        });
+
+        if let Err(e) = save::remove(&path) {
+            eprintln!("could not remove save file: {}", e);
+        }
+
+        save.filter(|s| {
+            Self::ask_resume(s).unwrap_or_else(|e| {
+                eprintln!("could not ask to resume: {}", e);
+                false
+            })
+        })
+    }
+
+    fn ask_resume(save: &SaveGame) -> MsResult<bool> {
+        let buttons = [
+            ButtonData {
+                flags: MessageBoxButtonFlag::RETURNKEY_DEFAULT,
+                button_id: 0,
+                text: "Resume",
+            },
+            ButtonData {
+                flags: MessageBoxButtonFlag::ESCAPEKEY_DEFAULT,
+                button_id: 1,
+                text: "New game",
+            },
+        ];
+
+        let message = format!(
+            "Resume your {} game at {:.1} seconds?",
+            save.difficulty.as_deref().unwrap_or("custom"),
+            save.stopwatch.elapsed().as_secs_f64()
+        );
+
+        let clicked = messagebox::show_message_box(
+            MessageBoxFlag::INFORMATION,
+            &buttons,
+            "minswpr",
+            &message,
+            None::<&Window>,
+            None::<MessageBoxColorScheme>,
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(match clicked {
+            ClickedButton::CustomButton(b) => b.button_id == 0,
+            ClickedButton::CloseButton => false,
+        })
+    }
+
+    fn load_high_scores() -> HighScores {
+        scores::path()
+            .and_then(|p| scores::read(&p))
+            .unwrap_or_else(|e| {
+                eprintln!("could not read high scores: {}", e);
+                HighScores::default()
+            })
+    }
+
+    fn load_stats() -> Stats {
+        stats::path()
+            .and_then(|p| stats::read(&p))
+            .unwrap_or_else(|e| {
+                eprintln!("could not read statistics: {}", e);
+                Stats::default()
+            })
+    }
+
+    fn draw_stats(&self, draw: &DrawContext, ctx: &Context) -> MsResult {
+        let name = {
+            let game = ctx.game().borrow();
+            let b = game.board();
+            scores::board_name(b.width(), b.height(), b.num_mines())
+        };
+        let secs = |d: Duration| format!("{:.3} seconds", d.as_secs_f64());
+
+        let mut lines = vec![format!("Statistics for {}", name)];
+        match ctx.stats().get(&name) {
+            Some(s) => {
+                lines.push(format!(
+                    "Won {} of {} games ({:.1}%)",
+                    s.won,
+                    s.played,
+                    s.win_rate() * 100.0
+                ));
+                lines.push(format!(
+                    "Current streak: {}, longest streak: {}",
+                    s.current_streak, s.longest_streak
+                ));
+                if let Some(best) = ctx.high_scores().get(&name).first() {
+                    lines.push(format!("Best time: {}", secs(best.elapsed)));
+                }
+                if let Some(average) = s.average_win_time() {
+                    lines.push(format!("Average win time: {}", secs(average)));
+                }
+                if let (Some(bbbv_per_sec), Some(efficiency)) = (s.bbbv_per_sec(), s.efficiency()) {
+                    lines.push(format!(
+                        "3BV/s: {:.3} (best {:.3}), efficiency: {:.0}%",
+                        bbbv_per_sec,
+                        s.best_bbbv_per_sec,
+                        efficiency * 100.0
+                    ));
+                }
+                lines.push(format!("Total time played: {}", secs(s.total_time)));
+            }
+            None => lines.push("No games played yet".to_string()),
+        }
+        lines.push("Press any key to close".to_string());
+
+        overlay::draw_overlay(draw, ctx.layout().dimen(), &lines, &self.config.overlay)
+    }
+
+    fn draw_paused(&self, draw: &DrawContext, dimen: Dimen) -> MsResult {
+        // fully opaque, so that the board can not be studied while paused
+        let config = OverlayConfig {
+            alpha: 255,
+            ..self.config.overlay.clone()
+        };
+        let lines = [
+            "Paused".to_string(),
+            "Press any key or click to resume".to_string(),
+        ];
+        overlay::draw_overlay(draw, dimen, &lines, &config)
+    }
+
+    fn draw_result(&self, draw: &DrawContext, dimen: Dimen, record: &GameRecord) -> MsResult {
+        let (title, result) = if record.did_win {
+            ("You won!", "Won")
+        } else {
+            ("Game over", "Lost")
+        };
+        let mut lines = vec![
+            title.to_string(),
+            format!(
+                "{} on {} in {:.3} seconds",
+                result,
+                scores::record_board_name(record),
+                record.elapsed.as_secs_f64()
+            ),
+        ];
+        if record.did_win {
+            lines.push(Self::describe_bbbv(record));
+            lines.push(Self::describe_clicks(record));
+        }
+        match record.hints_used {
+            0 => {}
+            1 => lines.push("1 hint used".to_string()),
+            n => lines.push(format!("{} hints used", n)),
+        }
+        lines.push("Press any key to close".to_string());
+
+        overlay::draw_overlay(draw, dimen, &lines, &self.config.overlay)
+    }
+
+    fn draw_name_entry(&self, draw: &DrawContext, dimen: Dimen, entry: &NameEntry) -> MsResult {
+        let record = entry.record();
+        let lines = [
+            "New high score!".to_string(),
+            format!(
+                "#{} on {} in {:.3} seconds",
+                entry.rank() + 1,
+                scores::record_board_name(record),
+                record.elapsed.as_secs_f64()
+            ),
+            Self::describe_bbbv(record),
+            Self::describe_clicks(record),
+            format!("Name: {}_", entry.name()),
+            "Enter to save, Escape to skip".to_string(),
+        ];
+        overlay::draw_overlay(draw, dimen, &lines, &self.config.overlay)
+    }
+
+    /// Describes the 3BV, 3BV/s and efficiency of a won game
+    fn describe_bbbv(record: &GameRecord) -> String {
+        format!(
+            "3BV {} at {:.3} 3BV/s, {:.0}% efficiency",
+            record.bbbv,
+            record.bbbv_per_sec(),
+            record.efficiency() * 100.0
+        )
+    }
+
+    /// Describes the clicks made during a game
+    fn describe_clicks(record: &GameRecord) -> String {
+        let c = &record.clicks;
+        format!(
+            "{} clicks ({} left, {} right, {} chord)",
+            c.total(),
+            c.left,
+            c.right,
+            c.chord
+        )
+    }
+
+    fn set_title(&self, draw: &DrawContext, seed: u64, status: Option<&str>) -> MsResult {
+        let title = match status {
+            Some(s) => format!("{} (seed: {}, {})", self.config.window.title, seed, s),
+            None => format!("{} (seed: {})", self.config.window.title, seed),
+        };
+        draw.canvas()
+            .window_mut()
+            .set_title(&title)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Scales the window and everything drawn in it to twice its size if
+    /// `is_zoomed` is true, or back to its normal size otherwise. SDL scales
+    /// the mouse positions back, so the layout does not need to know.
+    fn set_zoom(&self, draw: &DrawContext, dimen: Dimen, is_zoomed: bool) -> MsResult {
+        let scale = if is_zoomed { 2 } else { 1 };
+        let mut canvas = draw.canvas();
+        canvas.set_scale(scale as f32, scale as f32)?;
+        canvas
+            .window_mut()
+            .set_size(dimen.width() * scale, dimen.height() * scale)
+            .map_err(|e| e.to_string())
+    }
+
+    fn make_game(&self) -> MsResult<Game> {
         let bc = &self.config.board;
         let Dimen { x: w, y: h } = bc.dimen;
-        Board::new(w, h, bc.num_mines)
+        let mut board =
+            Board::new_deferred(w, h, bc.num_mines, bc.seed.unwrap_or_else(rand::random))?;
+        board.set_question_marks(bc.question_marks);
+        Ok(Game::new(board, bc.safe_area, bc.no_guess))
     }
 
     fn make_layout(&self, ctx: &Context) -> MsResult<Layout> {
@@ -154,7 +516,9 @@ impl Minswpr {
         let cc = &self.config.control;
 
         let board_draw = Box::new(DrawBoard::new(
-            ctx.board().clone(),
+            ctx.game().clone(),
+            ctx.heatmap().clone(),
+            ctx.cursor().clone(),
             self.config.board.cells.clone(),
         ));
         let board_width = board_draw.dimen().width();
@@ -167,8 +531,7 @@ impl Minswpr {
                         (ControlLayoutBuilder::default()
                             .config(&cc)
                             .board_width(board_width)
-                            .board(ctx.board())
-                            .stopwatch(ctx.stopwatch())
+                            .game(ctx.game())
                             .reset_button(ctx.button("reset"))
                             .build()?
                             .try_into()?): Layout,
@@ -226,21 +589,3 @@ where
         Self::new(config).unwrap()
     }
 }
-
-/// Represents different states the application can be in
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum GameState {
-    Unknown,
-    Ready,
-    Start,
-    Started,
-    Over(bool),
-    Reset,
-    Quit,
-}
-
-impl Default for GameState {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
@@ -0,0 +1,547 @@
+//! A terminal front end for minswpr, for playing where there is no window
+//! (like over SSH). The rules are those of `minswpr::game::Game`, and the
+//! board is configured by the same configuration file as the main binary.
+//! Keys are looked up in the same `[keybindings]`; the commands that need a
+//! window (save, heatmap, stats and zoom) do nothing here.
+//!
+//! Games played here are not saved, recorded as replays or counted toward the
+//! high scores and statistics.
+
+use clap::Clap;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+    MouseButton, MouseEvent,
+};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use minswpr::board::{Board, CellFlags};
+use minswpr::config::{self, difficulties, Command, Config, Key, KeyBindings};
+use minswpr::game::{self, Game, GameState};
+use minswpr::math::{Dimen, Point};
+use minswpr::{point, MsResult};
+use std::cmp;
+use std::io::{self, Stdout, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The column and row of the top left cell of the board
+const BOARD_POS: (u16, u16) = (1, 2);
+
+/// How long to wait for input before redrawing the stopwatch
+const TICK: Duration = Duration::from_millis(100);
+
+/// Shown below the help line when `game::Event::GuessRequired` is received
+const GUESS_WARNING: &str = "no board without guessing was found, this one may need a guess";
+
+fn main() -> MsResult {
+    let opts = Opts::parse();
+
+    let config = opts
+        .config
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config::resolve().unwrap());
+
+    let mut config = config::read_config(config)?;
+    opts.apply_to_config(&mut config)?;
+
+    let mut tui = Tui::new(config)?;
+    tui.check_size()?;
+
+    let _terminal = RawTerminal::enter().map_err(|e| e.to_string())?;
+    tui.start()
+}
+
+/// Play minswpr in the terminal
+#[derive(Clap)]
+#[clap(
+    version = "0.1.0",
+    author = "Walker J. Crouse <walkercrouse@hotmail.com>"
+)]
+struct Opts {
+    /// Path to the configuration file to use, resolved automatically if not
+    /// specified
+    #[clap(long = "config")]
+    config: Option<String>,
+    /// The cell-width of the board (overrides `config`)
+    #[clap(short = "w", long = "width")]
+    width: Option<usize>,
+    /// The cell-height of the board (overrides `config`)
+    #[clap(short = "h", long = "height")]
+    height: Option<usize>,
+    /// The amount of mines to place on the board (overrides config)
+    #[clap(short = "m", long = "num-mines")]
+    num_mines: Option<usize>,
+    /// The seed used to place the mines, for reproducing a board (overrides
+    /// `config`)
+    #[clap(long = "seed")]
+    seed: Option<u64>,
+    /// Try to generate boards that can be cleared without guessing. This is
+    /// best-effort: if no such board is found quickly, one that may need a guess
+    /// is played and a warning is shown (overrides `config`)
+    #[clap(long = "no-guess")]
+    no_guess: bool,
+    /// The difficulty mode (overrides `width`, `height`, and `num_mines`)
+    #[clap(long = "difficulty", possible_values = difficulties::ALL)]
+    difficulty: Option<String>,
+}
+
+impl Opts {
+    fn apply_to_config(&self, config: &mut Config) -> MsResult {
+        let bc = &mut config.board;
+        let Dimen { x: cw, y: ch } = bc.dimen;
+        bc.dimen = point!(self.width.unwrap_or(cw), self.height.unwrap_or(ch));
+        bc.num_mines = self.num_mines.unwrap_or(bc.num_mines);
+
+        if self.seed.is_some() {
+            bc.seed = self.seed;
+        }
+
+        if self.no_guess {
+            bc.no_guess = true;
+        }
+
+        match &self.difficulty {
+            Some(diff) => difficulties::apply_to_config(config, diff),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Puts the terminal in raw mode on an alternate screen with mouse capture,
+/// and restores it when dropped
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> crossterm::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            Show,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Tui {
+    config: Config,
+    game: Game,
+    cursor: Point<u32>,
+    warning: Option<&'static str>,
+    help: [String; 2],
+    out: Stdout,
+}
+
+impl Tui {
+    fn new(config: Config) -> MsResult<Self> {
+        let game = Self::make_game(&config)?;
+        let help = self::help_lines(&config.keybindings);
+        Ok(Self {
+            config,
+            game,
+            cursor: point!(0, 0),
+            warning: None,
+            help,
+            out: io::stdout(),
+        })
+    }
+
+    fn make_game(config: &Config) -> MsResult<Game> {
+        let bc = &config.board;
+        let Dimen { x: w, y: h } = bc.dimen;
+        let mut board =
+            Board::new_deferred(w, h, bc.num_mines, bc.seed.unwrap_or_else(rand::random))?;
+        board.set_question_marks(bc.question_marks);
+        Ok(Game::new(board, bc.safe_area, bc.no_guess))
+    }
+
+    /// Returns an `Err(String)` if the board does not fit in the terminal
+    fn check_size(&self) -> MsResult {
+        let (cols, rows) = terminal::size().map_err(|e| e.to_string())?;
+        let (width, height) = self.screen_dimen();
+        if width > cols || height > rows {
+            Err(format!(
+                "the terminal must be at least {}x{} to fit the board",
+                width, height
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the amount of columns and rows taken by the board, the control
+    /// line above it and the help and warning lines below it
+    fn screen_dimen(&self) -> (u16, u16) {
+        let board = self.game.board();
+        let help_width = self.help.iter().map(String::len).max().unwrap_or_default();
+        let width = cmp::max(board.width() as u16 * 2 + 1, help_width as u16);
+        let height = BOARD_POS.1 + board.height() as u16 + 4;
+        (width, height)
+    }
+
+    fn start(&mut self) -> MsResult {
+        self.clear()?;
+
+        loop {
+            self.draw().map_err(|e| e.to_string())?;
+
+            match Self::next_event().map_err(|e| e.to_string())? {
+                Some(Event::Key(key)) if self.is_quit(key) => return Ok(()),
+                Some(Event::Key(key)) => self.on_key(key)?,
+                Some(Event::Mouse(MouseEvent::Down(btn, col, row, _))) => {
+                    self.on_mouse_down(btn, col, row)
+                }
+                Some(Event::Resize(..)) => self.clear()?,
+                _ => {}
+            }
+        }
+    }
+
+    /// Waits for the next terminal `Event` and returns `Ok(None)` if there was
+    /// none within a `TICK`
+    fn next_event() -> crossterm::Result<Option<Event>> {
+        if event::poll(TICK)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn clear(&mut self) -> MsResult {
+        queue!(self.out, Clear(ClearType::All)).map_err(|e| e.to_string())
+    }
+
+    /// Returns `Some(Command)` bound to the specified `key` in the
+    /// `[keybindings]`, `None` if it is not bound
+    fn command(&self, key: KeyEvent) -> Option<Command> {
+        self::key_of(key).and_then(|k| self.config.keybindings.get(k))
+    }
+
+    /// Returns true if the specified `key` quits. Ctrl+C always does, since raw
+    /// mode keeps it from interrupting the process.
+    fn is_quit(&self, key: KeyEvent) -> bool {
+        let is_ctrl_c =
+            key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        is_ctrl_c || self.command(key) == Some(Command::Quit)
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> MsResult {
+        // any key resumes a paused game
+        if !self.game.resume().is_empty() {
+            return Ok(());
+        }
+
+        let command = match self.command(key) {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+
+        let Point { x, y } = self.cursor;
+        match command {
+            Command::CursorLeft => self.move_cursor(-1, 0),
+            Command::CursorRight => self.move_cursor(1, 0),
+            Command::CursorUp => self.move_cursor(0, -1),
+            Command::CursorDown => self.move_cursor(0, 1),
+            Command::Reveal => self.reveal(x, y),
+            Command::Flag => {
+                self.game.toggle_flag(x, y);
+            }
+            Command::Chord => {
+                self.game.chord(x, y);
+            }
+            Command::Hint => {
+                if let Some(hint) = self.game.hint() {
+                    self.cursor = hint.pos();
+                }
+            }
+            Command::Undo => {
+                self.game.undo();
+            }
+            Command::Redo => {
+                self.game.redo();
+            }
+            Command::Pause => {
+                self.game.pause();
+            }
+            Command::Reset => {
+                self.game = Self::make_game(&self.config)?;
+                self.warning = None;
+            }
+            Command::Save | Command::Heatmap | Command::Stats | Command::Zoom | Command::Quit => {}
+        }
+        Ok(())
+    }
+
+    fn on_mouse_down(&mut self, btn: MouseButton, col: u16, row: u16) {
+        if !self.game.resume().is_empty() {
+            return;
+        }
+
+        let Point { x, y } = match self.cell_at(col, row) {
+            Some(p) => p,
+            None => return,
+        };
+
+        self.cursor = point!(x, y);
+
+        match btn {
+            MouseButton::Left => self.reveal(x, y),
+            MouseButton::Right => {
+                self.game.toggle_flag(x, y);
+            }
+            MouseButton::Middle => {
+                self.game.chord(x, y);
+            }
+        }
+    }
+
+    fn reveal(&mut self, x: u32, y: u32) {
+        if self.game.reveal(x, y).contains(&game::Event::GuessRequired) {
+            self.warning = Some(GUESS_WARNING);
+        }
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let board = self.game.board();
+        let clamp = |v: i32, max: usize| cmp::max(0, cmp::min(v, max as i32 - 1)) as u32;
+        self.cursor = point!(
+            clamp(self.cursor.x as i32 + dx, board.width()),
+            clamp(self.cursor.y as i32 + dy, board.height())
+        );
+    }
+
+    /// Returns `Some(Point<u32>)` with the board position of the cell drawn at
+    /// the specified column and row, `None` if there is no cell there
+    fn cell_at(&self, col: u16, row: u16) -> Option<Point<u32>> {
+        let (bx, by) = BOARD_POS;
+        if col < bx || row < by {
+            return None;
+        }
+
+        let p = point!((col - bx) as u32 / 2, (row - by) as u32);
+        self.game.board().get_cell(p.x, p.y).map(|_| p)
+    }
+
+    fn draw(&mut self) -> crossterm::Result<()> {
+        self.draw_control()?;
+        self.draw_board()?;
+
+        let help_row = BOARD_POS.1 + self.game.board().height() as u16 + 1;
+        queue!(
+            self.out,
+            MoveTo(0, help_row),
+            Print(&self.help[0]),
+            MoveTo(0, help_row + 1),
+            Print(&self.help[1]),
+            MoveTo(0, help_row + 2),
+            Clear(ClearType::UntilNewLine),
+            Print(self.warning.unwrap_or_default())
+        )?;
+
+        self.out.flush()?;
+        Ok(())
+    }
+
+    /// Draws the flag counter, the game status and the stopwatch above the
+    /// board
+    fn draw_control(&mut self) -> crossterm::Result<()> {
+        let normal_val = |i: i64| cmp::max(-99, cmp::min(999, i));
+        let board = self.game.board();
+        let width = board.width() * 2 + 1;
+
+        let flags_remaining = normal_val(board.num_mines() as i64 - board.count_flags() as i64);
+        let stopwatch_config = &self.config.control.stopwatch;
+        let elapsed = stopwatch_config
+            .format
+            .format(self.game.stopwatch().elapsed());
+        let status = match self.game.state() {
+            GameState::Over(true) => "B)",
+            GameState::Over(false) => "X(",
+            GameState::Paused => "||",
+            _ => ":)",
+        };
+
+        let counter_color = self::color(self.config.control.flag_counter.text_color);
+        let stopwatch_color = self::color(stopwatch_config.text_color);
+        let elapsed = format!("{:0>3}", elapsed);
+        let padding = width.saturating_sub(3 + elapsed.len() + status.len()) / 2;
+
+        queue!(
+            self.out,
+            MoveTo(0, 0),
+            SetForegroundColor(counter_color),
+            Print(format!(" {:03}", flags_remaining)),
+            ResetColor,
+            Print(format!("{:>1$}", status, padding + status.len())),
+            SetForegroundColor(stopwatch_color),
+            Print(format!("{:>1$}", elapsed, padding + elapsed.len())),
+            ResetColor,
+        )
+    }
+
+    fn draw_board(&mut self) -> crossterm::Result<()> {
+        let (bx, by) = BOARD_POS;
+        let (width, height) = {
+            let b = self.game.board();
+            (b.width() as u32, b.height() as u32)
+        };
+
+        for y in 0..height {
+            queue!(self.out, MoveTo(bx, by + y as u16))?;
+            for x in 0..width {
+                let (c, fg, bg) = self.cell_style(x, y);
+                let is_cursor = self.cursor == point!(x, y);
+
+                if is_cursor {
+                    queue!(self.out, SetAttribute(Attribute::Reverse))?;
+                }
+                if let Some(fg) = fg {
+                    queue!(self.out, SetForegroundColor(fg))?;
+                }
+                if let Some(bg) = bg {
+                    queue!(self.out, SetBackgroundColor(bg))?;
+                }
+
+                queue!(
+                    self.out,
+                    Print(c),
+                    SetAttribute(Attribute::Reset),
+                    Print(' ')
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the character used to draw the cell at the specified position
+    /// with its foreground and background colors
+    fn cell_style(&self, x: u32, y: u32) -> (char, Option<Color>, Option<Color>) {
+        let board = self.game.board();
+        let config = &self.config.board.cells;
+        let cell = board.cell(x, y);
+        let is_mine = cell.contains(CellFlags::MINE);
+
+        if self.game.is_paused() {
+            // hide the board so that it can not be studied while paused
+            ('#', None, None)
+        } else if cell.contains(CellFlags::REVEALED) {
+            if is_mine {
+                ('*', Some(self::color(config.mines.revealed_color)), None)
+            } else {
+                match board.count_adjacent_mines(x, y) {
+                    0 => (' ', None, None),
+                    n => (
+                        std::char::from_digit(n as u32, 10).unwrap(),
+                        Some(self::color(config.text_color)),
+                        None,
+                    ),
+                }
+            }
+        } else if cell.contains(CellFlags::FLAG) {
+            ('F', Some(self::color(config.flags.color)), None)
+        } else if cell.contains(CellFlags::QUESTION) {
+            (
+                config.question.glyph.chars().next().unwrap_or('?'),
+                None,
+                None,
+            )
+        } else if is_mine && self.game.state() == GameState::Over(false) {
+            // show the mines that were not found
+            ('*', None, None)
+        } else if board.highlight() == Some(point!(x, y)) {
+            ('.', None, Some(self::color(config.hint_color)))
+        } else {
+            ('.', None, None)
+        }
+    }
+}
+
+/// Returns `Some(Key)` with the key of the `[keybindings]` the specified
+/// terminal `key` is named as, `None` if it can not be bound
+fn key_of(key: KeyEvent) -> Option<Key> {
+    let code = match key.code {
+        KeyCode::Char(' ') => config::KeyCode::Space,
+        KeyCode::Char(c) if c.is_ascii_graphic() => config::KeyCode::Char(c.to_ascii_uppercase()),
+        KeyCode::F(n) if (1..=12).contains(&n) => config::KeyCode::F(n),
+        KeyCode::Left => config::KeyCode::Left,
+        KeyCode::Right => config::KeyCode::Right,
+        KeyCode::Up => config::KeyCode::Up,
+        KeyCode::Down => config::KeyCode::Down,
+        KeyCode::Enter => config::KeyCode::Return,
+        KeyCode::Esc => config::KeyCode::Escape,
+        KeyCode::Backspace => config::KeyCode::Backspace,
+        KeyCode::Tab => config::KeyCode::Tab,
+        KeyCode::Delete => config::KeyCode::Delete,
+        KeyCode::Insert => config::KeyCode::Insert,
+        KeyCode::Home => config::KeyCode::Home,
+        KeyCode::End => config::KeyCode::End,
+        KeyCode::PageUp => config::KeyCode::PageUp,
+        KeyCode::PageDown => config::KeyCode::PageDown,
+        _ => return None,
+    };
+
+    Some(Key {
+        code,
+        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+    })
+}
+
+/// Returns the two help lines shown below the board, naming the first key
+/// bound to each command. Commands without a key are left out.
+fn help_lines(bindings: &KeyBindings) -> [String; 2] {
+    let entry = |commands: &[Command], label: &str| {
+        let keys = commands
+            .iter()
+            .map(|c| bindings.keys(*c).first().map(Key::to_string))
+            .collect::<Option<Vec<_>>>()?;
+        Some(format!("{}: {}", keys.join("/"), label))
+    };
+    let line =
+        |entries: Vec<Option<String>>| entries.into_iter().flatten().collect::<Vec<_>>().join("  ");
+
+    [
+        line(vec![
+            entry(
+                &[
+                    Command::CursorLeft,
+                    Command::CursorRight,
+                    Command::CursorUp,
+                    Command::CursorDown,
+                ],
+                "move",
+            ),
+            entry(&[Command::Reveal], "reveal"),
+            entry(&[Command::Flag], "flag"),
+            entry(&[Command::Chord], "chord"),
+        ]),
+        line(vec![
+            entry(&[Command::Hint], "hint"),
+            entry(&[Command::Undo, Command::Redo], "undo/redo"),
+            entry(&[Command::Pause], "pause"),
+            entry(&[Command::Reset], "new game"),
+            entry(&[Command::Quit], "quit"),
+        ]),
+    ]
+}
+
+fn color(c: sdl2::pixels::Color) -> Color {
+    Color::Rgb {
+        r: c.r,
+        g: c.g,
+        b: c.b,
+    }
+}
@@ -0,0 +1,71 @@
+use super::CellFlags;
+use crate::math::Point;
+
+/// A reversible action on a `Board`, applied with `Board::apply`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Reveals a cell like `Board::reveal_from`
+    Reveal(Point<u32>),
+    /// Reveals the neighbors of a satisfied number like `Board::reveal_area`
+    RevealArea(Point<u32>),
+    /// Flags or unflags a cell like `Board::toggle_flag`
+    ToggleFlag(Point<u32>),
+}
+
+/// The index of a cell changed by an `Action` and the cell's flags before and
+/// after
+pub(super) type Change = (usize, CellFlags, CellFlags);
+
+/// An applied `Action` along with every cell it changed
+#[derive(Debug, Clone)]
+pub(super) struct Entry {
+    pub action: Action,
+    pub changes: Vec<Change>,
+}
+
+/// The actions applied to a `Board` that can be undone and redone
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vec<Entry>,
+    undone: Vec<Entry>,
+}
+
+impl History {
+    /// Returns true if there is an action to undo
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    /// Returns true if there is an undone action to redo
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Records a newly applied action, discarding the undone actions
+    pub(super) fn push(&mut self, entry: Entry) {
+        self.done.push(entry);
+        self.undone.clear();
+    }
+
+    /// Adds more changed cells to the last applied action, so that they are
+    /// undone and redone along with it
+    pub(super) fn extend_last(&mut self, changes: Vec<Change>) {
+        if let Some(entry) = self.done.last_mut() {
+            entry.changes.extend(changes);
+        }
+    }
+
+    /// Moves the last applied action to the undone actions and returns it
+    pub(super) fn undo(&mut self) -> Option<&Entry> {
+        let entry = self.done.pop()?;
+        self.undone.push(entry);
+        self.undone.last()
+    }
+
+    /// Moves the last undone action back to the applied actions and returns it
+    pub(super) fn redo(&mut self) -> Option<&Entry> {
+        let entry = self.undone.pop()?;
+        self.done.push(entry);
+        self.done.last()
+    }
+}
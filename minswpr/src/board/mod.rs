@@ -1,9 +1,21 @@
+mod history;
 #[cfg(test)]
 mod tests;
+mod text;
+
+pub use self::history::{Action, History};
+
+use self::history::{Change, Entry};
 
 use crate::math::{self, Point};
+use crate::solver;
 use crate::MsResult;
 use itertools::Itertools;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp;
+use std::convert::TryFrom;
 
 bitflags! {
     #[derive(Default)]
@@ -12,26 +24,163 @@ bitflags! {
         const MINE = 0b0000_0010;
         const FLAG = 0b0000_0100;
         const PRESSED = 0b0000_1000;
+        const QUESTION = 0b0001_0000;
+    }
+}
+
+impl Serialize for CellFlags {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        self.bits().serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for CellFlags {
+    fn deserialize<D: Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        let bits = u8::deserialize(des)?;
+        Self::from_bits(bits).ok_or_else(|| de::Error::custom(format!("invalid cell: {}", bits)))
     }
 }
 
 /// Manages the state of the main game board
-#[derive(Debug, Clone)]
+///
+/// A `Board` can be written as and read from a text grid with `Display` and
+/// `FromStr`, see the `Display` implementation for the format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawBoard")]
 pub struct Board {
     width: usize,
     height: usize,
     num_mines: usize,
     cells: Vec<CellFlags>,
+    is_generated: bool,
+    seed: u64,
+    #[serde(skip)]
+    question_marks: bool,
+    #[serde(skip)]
+    highlight: Option<Point<u32>>,
+    #[serde(skip)]
+    history: History,
+    /// The amount of mines adjacent to each cell, kept up to date whenever a
+    /// mine is placed or removed
+    #[serde(skip)]
+    adjacent_mines: Vec<u8>,
+}
+
+/// The serialized fields of a `Board`, checked before the board is created
+#[derive(Deserialize)]
+struct RawBoard {
+    width: usize,
+    height: usize,
+    num_mines: usize,
+    cells: Vec<CellFlags>,
+    is_generated: bool,
+    seed: u64,
+}
+
+impl TryFrom<RawBoard> for Board {
+    type Error = String;
+
+    fn try_from(raw: RawBoard) -> Result<Self, Self::Error> {
+        if raw.cells.len() != Self::num_cells(raw.width, raw.height)? {
+            return Err("the cells do not match the dimensions of the board".to_string());
+        }
+
+        // the flag counter and the win depend on the amount of mines
+        let mines = raw
+            .cells
+            .iter()
+            .filter(|c| c.contains(CellFlags::MINE))
+            .count();
+        let expected = if raw.is_generated { raw.num_mines } else { 0 };
+        if mines != expected {
+            return Err(format!(
+                "the board has {} mines but expects {}",
+                mines, expected
+            ));
+        }
+
+        let mut board = Self::new_deferred(raw.width, raw.height, raw.num_mines, raw.seed)?;
+        board.cells = raw.cells;
+        board.is_generated = raw.is_generated;
+        board.count_mines();
+        Ok(board)
+    }
 }
 
 impl Board {
+    /// The amount of cells `Board::generate` may lay out before giving up on
+    /// finding a layout that can be solved without guessing. Solving a layout
+    /// takes longer the more cells it has, so larger boards get fewer attempts
+    /// and the first click stays responsive on any board.
+    pub const NO_GUESS_CELL_BUDGET: usize = 100_000;
+
+    /// The most cells a board may have, so that the dimensions read from a
+    /// replay or save can not overflow or exhaust memory when the cells are
+    /// allocated
+    pub const MAX_CELLS: usize = 10_000_000;
+
     /// Creates a new board with the specified cell `width`, `height`, and
     /// number of mines `num_mines`. Returns `Ok(Board)` if successful,
     /// `Err(String)` otherwise.
     ///
     /// `num_mines` must not exceed the cell area of the board
     pub fn new(width: usize, height: usize, num_mines: usize) -> MsResult<Self> {
-        let num_cells = width * height;
+        Self::with_seed(width, height, num_mines, rand::random())
+    }
+
+    /// Creates a new board like `Board::new` but places the mines using the
+    /// specified `seed`. The same seed, dimensions and number of mines always
+    /// produce the same layout.
+    pub fn with_seed(width: usize, height: usize, num_mines: usize, seed: u64) -> MsResult<Self> {
+        let mut board = Self::new_deferred(width, height, num_mines, seed)?;
+        board.place_mines(&mut ChaCha8Rng::seed_from_u64(seed), &[]);
+        Ok(board)
+    }
+
+    /// Creates a new board with mines in the cells at the specified `mines`
+    /// indices, counted row by row, like the layout of a replay. The board
+    /// counts as generated. Returns `Err(String)` if an index is outside of the
+    /// board or repeated.
+    pub fn with_mines(width: usize, height: usize, mines: &[usize], seed: u64) -> MsResult<Self> {
+        let mut board = Self::new_deferred(width, height, mines.len(), seed)?;
+
+        for &i in mines {
+            match board.cells.get(i) {
+                None => return Err(format!("mine {} is outside of the board", i)),
+                Some(c) if c.contains(CellFlags::MINE) => {
+                    return Err(format!("mine {} is placed twice", i))
+                }
+                Some(_) => {
+                    let p = board.point(i);
+                    board.set_mine(p.x, p.y, true);
+                }
+            }
+        }
+
+        board.is_generated = true;
+        Ok(board)
+    }
+
+    /// Creates a new board with the specified cell `width`, `height`, and
+    /// number of mines `num_mines` without placing any mines. The mines are
+    /// placed using the specified `seed` once `Board::generate` is called with
+    /// the position of the first revealed cell. Returns `Ok(Board)` if
+    /// successful, `Err(String)` otherwise.
+    ///
+    /// The board must have at least one cell and no more than
+    /// `Board::MAX_CELLS`, and `num_mines` must not exceed the cell area of the
+    /// board
+    pub fn new_deferred(
+        width: usize,
+        height: usize,
+        num_mines: usize,
+        seed: u64,
+    ) -> MsResult<Self> {
+        let num_cells = Self::num_cells(width, height)?;
+
+        if num_cells == 0 {
+            return Err("the board must have at least one cell".to_string());
+        }
 
         if num_mines > num_cells {
             return Err("num_mines must not exceed the area of the board".to_string());
@@ -41,10 +190,31 @@ impl Board {
             width,
             height,
             num_mines,
-            cells: Self::make_cells(num_cells, &math::gen_rand_unique(num_mines, 0, num_cells)),
+            cells: vec![CellFlags::default(); num_cells],
+            is_generated: false,
+            seed,
+            question_marks: false,
+            highlight: None,
+            history: History::default(),
+            adjacent_mines: vec![0; num_cells],
         })
     }
 
+    /// Returns the amount of cells on a board of the specified `width` and
+    /// `height`, or `Err(String)` if there would be more than
+    /// `Board::MAX_CELLS`
+    pub fn num_cells(width: usize, height: usize) -> MsResult<usize> {
+        width
+            .checked_mul(height)
+            .filter(|n| *n <= Self::MAX_CELLS)
+            .ok_or_else(|| {
+                format!(
+                    "the board must not have more than {} cells",
+                    Self::MAX_CELLS
+                )
+            })
+    }
+
     /// Returns the cell width of the board
     pub fn width(&self) -> usize {
         self.width
@@ -60,6 +230,82 @@ impl Board {
         self.num_mines
     }
 
+    /// Returns the seed used to place the mines on the board
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns true if the mines have been placed on the board
+    pub fn is_generated(&self) -> bool {
+        self.is_generated
+    }
+
+    /// Places the mines on a board created with `Board::new_deferred`,
+    /// guaranteeing that the cell at the specified `x` and `y` position is
+    /// mine-free. If `safe_area` is true, the cell's neighbors are kept clear as
+    /// well. If `no_guess` is true, layouts are re-rolled until the board can be
+    /// cleared from the cell using deduction only, giving up after
+    /// `Board::max_no_guess_attempts` layouts. This method has no effect if the
+    /// mines have already been placed.
+    ///
+    /// If the board is too crowded to keep the neighbors clear, only the cell
+    /// itself is guaranteed to be safe. If every cell is a mine, nothing is.
+    ///
+    /// The layout is determined by the board's seed, dimensions, number of
+    /// mines and the safe cells, so replaying the same first click on a board
+    /// with the same seed produces the same layout.
+    ///
+    /// Returns false if a no-guess layout was requested but could not be found,
+    /// true otherwise
+    pub fn generate(&mut self, x: u32, y: u32, safe_area: bool, no_guess: bool) -> bool {
+        if self.is_generated {
+            return true;
+        }
+
+        let num_cells = self.cells.len();
+        let mut safe = vec![point!(x, y)];
+        if safe_area {
+            safe.extend(self.neighbors(x, y));
+        }
+
+        if self.num_mines > num_cells.saturating_sub(safe.len()) {
+            safe.truncate(1);
+        }
+
+        if self.num_mines == num_cells {
+            safe.clear();
+        }
+
+        let safe = safe
+            .iter()
+            .map(|p| Self::index(p.x, p.y, self.width))
+            .collect::<Vec<_>>();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.place_mines(&mut rng, &safe);
+
+        if !no_guess {
+            return true;
+        }
+
+        for _ in 1..self.max_no_guess_attempts() {
+            if solver::is_solvable(self, point!(x, y)) {
+                return true;
+            }
+            self.clear_all(CellFlags::MINE);
+            self.place_mines(&mut rng, &safe);
+        }
+
+        solver::is_solvable(self, point!(x, y))
+    }
+
+    /// Returns the amount of layouts `Board::generate` tries before giving up on
+    /// finding one that can be solved without guessing, which depends only on
+    /// the size of the board so that the same seed always gives the same layout
+    pub fn max_no_guess_attempts(&self) -> usize {
+        cmp::max(1, Self::NO_GUESS_CELL_BUDGET / self.cells.len())
+    }
+
     /// Returns a reference to the internal `Vec<CellFlags>`
     pub fn cells(&self) -> &Vec<CellFlags> {
         &self.cells
@@ -71,10 +317,20 @@ impl Board {
         self.cells[Self::index(x, y, self.width)]
     }
 
-    /// Returns the cell at the specified `x` and `y` position. Panics if the
-    /// cell is not present
-    pub fn cell_mut(&mut self, x: u32, y: u32) -> &mut CellFlags {
-        &mut self.cells[Self::index(x, y, self.width)]
+    /// Adds the specified `CellFlags` value to the cell at the specified `x`
+    /// and `y` position. Panics if the cell is not present
+    ///
+    /// `CellFlags::MINE` is added with `Board::set_mine`
+    pub fn insert_flags(&mut self, x: u32, y: u32, cell_flags: CellFlags) {
+        self.set_flags(x, y, cell_flags, true)
+    }
+
+    /// Removes the specified `CellFlags` value from the cell at the specified
+    /// `x` and `y` position. Panics if the cell is not present
+    ///
+    /// `CellFlags::MINE` is removed with `Board::set_mine`
+    pub fn remove_flags(&mut self, x: u32, y: u32, cell_flags: CellFlags) {
+        self.set_flags(x, y, cell_flags, false)
     }
 
     /// Returns Some(&CellFlags) if there is a cell at the specified `x` and `y`
@@ -83,10 +339,38 @@ impl Board {
         self.cells.get(Self::index(x, y, self.width))
     }
 
-    /// Returns Some(&mut CellFlags) if there is a cell at the specified `x` and
-    /// `y` position. Returns `None` otherwise.
-    pub fn get_cell_mut(&mut self, x: u32, y: u32) -> Option<&mut CellFlags> {
-        self.cells.get_mut(Self::index(x, y, self.width))
+    /// Returns `Some(Point<u32>)` with the position of the highlighted cell, or
+    /// `None` if no cell is highlighted. The highlight is kept apart from the
+    /// `CellFlags` of the cell.
+    pub fn highlight(&self) -> Option<Point<u32>> {
+        self.highlight
+    }
+
+    /// Highlights the cell at the specified position, or removes the highlight
+    /// if `None`
+    pub fn set_highlight(&mut self, highlight: Option<Point<u32>>) {
+        self.highlight = highlight
+    }
+
+    /// Adds a mine to the cell at the specified `x` and `y` position if
+    /// `is_mine` is true, removes it otherwise. Panics if the cell is not
+    /// present
+    ///
+    /// This does not change `Board::num_mines`
+    pub fn set_mine(&mut self, x: u32, y: u32, is_mine: bool) {
+        let i = Self::index(x, y, self.width);
+        if self.cells[i].contains(CellFlags::MINE) == is_mine {
+            return;
+        }
+
+        self.cells[i].set(CellFlags::MINE, is_mine);
+        for j in self.neighbor_indices(i) {
+            if is_mine {
+                self.adjacent_mines[j] += 1;
+            } else {
+                self.adjacent_mines[j] -= 1;
+            }
+        }
     }
 
     /// Removes the specified `CellFlags` value from all cells on the board
@@ -94,24 +378,55 @@ impl Board {
         for cell in &mut self.cells {
             cell.remove(cell_flags);
         }
+
+        if cell_flags.contains(CellFlags::MINE) {
+            self.adjacent_mines.iter_mut().for_each(|n| *n = 0);
+        }
+    }
+
+    /// Returns true if `Board::toggle_flag` marks cells with question marks
+    pub fn question_marks(&self) -> bool {
+        self.question_marks
+    }
+
+    /// Sets whether `Board::toggle_flag` marks cells with question marks after
+    /// flags
+    pub fn set_question_marks(&mut self, question_marks: bool) {
+        self.question_marks = question_marks
     }
 
     /// Toggles whether the cell at the specified `x` and `y` position is a
-    /// flag. This method has no effect if there is no cell at the position
+    /// flag. If question marks are enabled, a flag is replaced by a question
+    /// mark first, so that the cell cycles through hidden, flag and question
+    /// mark. This method has no effect if there is no cell at the position
     /// or if the cell has been previously revealed.
     ///
     /// Returns true if a cell was affected, false otherwise
     pub fn toggle_flag(&mut self, x: u32, y: u32) -> bool {
-        if let Some(c) = self.get_cell_mut(x, y) {
-            if !c.contains(CellFlags::REVEALED) {
-                c.toggle(CellFlags::FLAG);
-                return true;
+        self.toggle_flag_change(x, y).is_some()
+    }
+
+    fn toggle_flag_change(&mut self, x: u32, y: u32) -> Option<Change> {
+        let i = Self::index(x, y, self.width);
+        let cell = *self
+            .get_cell(x, y)
+            .filter(|c| !c.contains(CellFlags::REVEALED))?;
+
+        let question_marks = self.question_marks;
+        Some(self.change_cell(i, |c| {
+            if cell.contains(CellFlags::FLAG) {
+                c.remove(CellFlags::FLAG);
+                c.set(CellFlags::QUESTION, question_marks);
+            } else if cell.contains(CellFlags::QUESTION) {
+                c.remove(CellFlags::QUESTION);
+            } else {
+                c.insert(CellFlags::FLAG);
             }
-        }
-        false
+        }))
     }
 
-    /// Counts and returns the number of flagged cells on the board
+    /// Counts and returns the number of flagged cells on the board. Question
+    /// marks are not counted
     pub fn count_flags(&self) -> usize {
         self.cells
             .iter()
@@ -122,15 +437,13 @@ impl Board {
     /// Counts and returns the number of mines adjacent (horizontal, vertical,
     /// diagonal) to the cell at the specified `x` and `y` position
     pub fn count_adjacent_mines(&self, x: u32, y: u32) -> usize {
-        self.filter_neighbors(x, y, |c| c.contains(CellFlags::MINE))
-            .len()
+        self.adjacent_mines[Self::index(x, y, self.width)] as usize
     }
 
     /// Counts and returns the number of flags adjacent (horizontal, vertical,
     /// diagonal) to the cell at the specified `x` and `y` position
     pub fn count_adjacent_flags(&self, x: u32, y: u32) -> usize {
-        self.filter_neighbors(x, y, |c| c.contains(CellFlags::FLAG))
-            .len()
+        self.count_adjacent(Self::index(x, y, self.width), CellFlags::FLAG)
     }
 
     /// Returns a `Vec<Point<u32>>` of cell positions that do not contain
@@ -144,48 +457,101 @@ impl Board {
             .collect()
     }
 
-    /// Recursively reveals cells on the board from the specified `x` abd `y`
-    /// position. This method reveals cells starting with the positions
-    /// neighbors, expanding outwards until a mine or a cell with no adjacent
-    /// mines is reached.
+    /// Returns `Some(usize)` with the 3BV of the board: the least amount of
+    /// clicks needed to clear it without flagging or chording. Every opening
+    /// of cells without adjacent mines takes one click, as does every other
+    /// safe cell that is not on the edge of an opening. Returns `None` if the
+    /// mines have not been placed yet.
+    pub fn bbbv(&self) -> Option<usize> {
+        if !self.is_generated {
+            return None;
+        }
+
+        let is_safe = |i: usize| !self.cells[i].contains(CellFlags::MINE);
+        let mut is_opened = vec![false; self.cells.len()];
+        let mut bbbv = 0;
+
+        for i in 0..self.cells.len() {
+            if is_opened[i] || !is_safe(i) || self.adjacent_mines[i] > 0 {
+                continue;
+            }
+
+            // open the cells that one click here would reveal
+            bbbv += 1;
+            is_opened[i] = true;
+            let mut stack = vec![i];
+            while let Some(j) = stack.pop() {
+                if self.adjacent_mines[j] > 0 {
+                    continue;
+                }
+
+                for k in self.neighbor_indices(j) {
+                    if !is_opened[k] {
+                        is_opened[k] = true;
+                        stack.push(k);
+                    }
+                }
+            }
+        }
+
+        Some(
+            bbbv + (0..self.cells.len())
+                .filter(|i| is_safe(*i) && !is_opened[*i])
+                .count(),
+        )
+    }
+
+    /// Reveals cells on the board from the specified `x` and `y` position.
+    /// This method reveals cells starting with the position's neighbors,
+    /// expanding outwards until a mine or a cell with adjacent mines is reached.
+    /// Flagged cells are never revealed, but cells marked with a question mark
+    /// are.
+    ///
+    /// The cells are filled iteratively, so that boards of any size can be
+    /// opened in one click.
     ///
     /// Returns the amount of cells that were revealed.
     pub fn reveal_from(&mut self, x: u32, y: u32) -> u32 {
-        let mut count = 0;
-        self._reveal_from(x, y, &mut count);
-        count
+        self.reveal_from_changes(x, y).len() as u32
     }
 
-    fn _reveal_from(&mut self, x: u32, y: u32, count: &mut u32) {
+    fn reveal_from_changes(&mut self, x: u32, y: u32) -> Vec<Change> {
+        let mut changes = vec![];
+
         // make sure the cell hasn't been previously revealed
         // or..
         // make sure the cell isn't flagged
-        let cell = match self
-            .get_cell_mut(x, y)
-            .filter(|c| !c.contains(CellFlags::REVEALED))
-            .filter(|c| !c.contains(CellFlags::FLAG))
-        {
-            Some(c) => c,
-            None => return,
+        let i = match self.get_cell(x, y) {
+            Some(c) if !c.intersects(CellFlags::REVEALED | CellFlags::FLAG) => {
+                Self::index(x, y, self.width)
+            }
+            _ => return changes,
         };
 
-        // reveal the current cell
-        cell.insert(CellFlags::REVEALED);
-        *count += 1;
+        // cells are revealed as they are pushed, so that no cell is pushed twice
+        changes.push(self.change_cell(i, Self::reveal_cell));
+        let mut stack = vec![i];
 
-        // if the revealed cell was a mine, stop revealing cells
-        // or...
-        // if the revealed cell is touching a mine, stop revealing cells
-        if cell.contains(CellFlags::MINE) || self.count_adjacent_mines(x, y) > 0 {
-            return;
+        while let Some(i) = stack.pop() {
+            // if the revealed cell was a mine, stop revealing cells
+            // or...
+            // if the revealed cell is touching a mine, stop revealing cells
+            if self.cells[i].contains(CellFlags::MINE) || self.adjacent_mines[i] > 0 {
+                continue;
+            }
+
+            // reveal all adjacent cells that are not a mine
+            for j in self.neighbor_indices(i) {
+                if !self.cells[j]
+                    .intersects(CellFlags::MINE | CellFlags::REVEALED | CellFlags::FLAG)
+                {
+                    changes.push(self.change_cell(j, Self::reveal_cell));
+                    stack.push(j);
+                }
+            }
         }
 
-        // reveal all adjacent cells that are not a mine
-        self.filter_neighbors(x, y, |c| {
-            !c.contains(CellFlags::MINE) && !c.contains(CellFlags::REVEALED)
-        })
-        .iter()
-        .for_each(|p| self._reveal_from(p.x, p.y, count));
+        changes
     }
 
     /// Implements the curious middle-click behavior present in the original
@@ -195,6 +561,13 @@ impl Board {
     ///
     /// Returns a `Vec<Point<u32>>` of cell positions that were revealed
     pub fn reveal_area(&mut self, x: u32, y: u32) -> Vec<Point<u32>> {
+        self.reveal_area_changes(x, y)
+            .iter()
+            .map(|(i, _, _)| self.point(*i))
+            .collect()
+    }
+
+    fn reveal_area_changes(&mut self, x: u32, y: u32) -> Vec<Change> {
         // only accept revealed cells
         let cell = self
             .get_cell(x, y)
@@ -216,55 +589,186 @@ impl Board {
         }
 
         // reveal the cells neighbors that are not revealed and not flagged
-        let neighbors = self.filter_neighbors(x, y, |c| {
+        self.filter_neighbors(x, y, |c| {
             !c.contains(CellFlags::REVEALED) && !c.contains(CellFlags::FLAG)
-        });
+        })
+        .iter()
+        .map(|p| self.change_cell(Self::index(p.x, p.y, self.width), Self::reveal_cell))
+        .collect()
+    }
+
+    /// Applies the specified `Action` and records it in the `History` so that
+    /// it can be undone. Returns a `Vec<Point<u32>>` of cell positions that
+    /// were changed; actions that change nothing are not recorded.
+    pub fn apply(&mut self, action: Action) -> Vec<Point<u32>> {
+        let changes = match action {
+            Action::Reveal(p) => self.reveal_from_changes(p.x, p.y),
+            Action::RevealArea(p) => self.reveal_area_changes(p.x, p.y),
+            Action::ToggleFlag(p) => self.toggle_flag_change(p.x, p.y).into_iter().collect(),
+        };
 
-        for neighbor in &neighbors {
-            self.cell_mut(neighbor.x, neighbor.y)
-                .insert(CellFlags::REVEALED);
+        let changed = changes.iter().map(|(i, _, _)| self.point(*i)).collect();
+
+        if !changes.is_empty() {
+            self.history.push(Entry { action, changes });
         }
 
-        neighbors
+        changed
+    }
+
+    /// Flags every cell that has not been revealed, replacing question marks.
+    /// The flags are part of the last `Action` applied with `Board::apply`, so
+    /// that undoing the action removes them again.
+    pub fn flag_remaining(&mut self) {
+        let remaining = (0..self.cells.len())
+            .filter(|i| !self.cells[*i].intersects(CellFlags::REVEALED | CellFlags::FLAG))
+            .collect::<Vec<_>>();
+
+        let changes = remaining
+            .into_iter()
+            .map(|i| {
+                self.change_cell(i, |c| {
+                    c.remove(CellFlags::QUESTION);
+                    c.insert(CellFlags::FLAG);
+                })
+            })
+            .collect();
+
+        self.history.extend_last(changes);
+    }
+
+    /// Reverts the last `Action` applied with `Board::apply` and returns it, or
+    /// returns `None` if there is nothing to undo
+    pub fn undo(&mut self) -> Option<Action> {
+        let entry = self.history.undo()?;
+        for (i, before, _) in &entry.changes {
+            self.cells[*i] = *before | (self.cells[*i] & CellFlags::PRESSED);
+        }
+        Some(entry.action)
+    }
+
+    /// Applies the last `Action` reverted with `Board::undo` again and returns
+    /// it, or returns `None` if there is nothing to redo
+    pub fn redo(&mut self) -> Option<Action> {
+        let entry = self.history.redo()?;
+        for (i, _, after) in &entry.changes {
+            self.cells[*i] = *after | (self.cells[*i] & CellFlags::PRESSED);
+        }
+        Some(entry.action)
+    }
+
+    /// Returns the `History` of the actions applied with `Board::apply`
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Returns true if a cell containing a mine has been revealed
+    pub fn has_revealed_mine(&self) -> bool {
+        self.cells
+            .iter()
+            .any(|c| c.contains(CellFlags::REVEALED | CellFlags::MINE))
     }
 
     /// Returns a `Vec<Point<u32>>` of cell positions that are adjacent
     /// (horizontal, vertical, diagonal) to the specified `x` and `y` positions
     pub fn neighbors(&self, x: u32, y: u32) -> Vec<Point<u32>> {
-        let x = x as i32;
-        let y = y as i32;
-        (x - 1..x + 2)
-            .cartesian_product(y - 1..y + 2)
-            .filter(|(nx, ny)| *nx >= 0 && *ny >= 0)
-            .filter(|(nx, ny)| *nx < self.width as i32 && *ny < self.height as i32)
-            .filter(|(nx, ny)| !(*nx == x && *ny == y))
-            .map(|(nx, ny)| (nx as u32, ny as u32))
-            .filter(|(nx, ny)| self.get_cell(*nx, *ny).is_some())
-            .map(|(nx, ny)| point!(nx, ny))
-            .collect()
+        self.iter_neighbors(x, y).collect()
+    }
+
+    /// Returns an iterator over the cell positions that are adjacent to the
+    /// specified `x` and `y` position, like `Board::neighbors` but without
+    /// allocating
+    pub fn iter_neighbors(&self, x: u32, y: u32) -> impl Iterator<Item = Point<u32>> + '_ {
+        self.neighbor_indices(Self::index(x, y, self.width))
+            .map(move |i| self.point(i))
+    }
+
+    fn set_flags(&mut self, x: u32, y: u32, cell_flags: CellFlags, value: bool) {
+        // keep the adjacent mine counts up to date
+        if cell_flags.contains(CellFlags::MINE) {
+            self.set_mine(x, y, value);
+        }
+        self.cells[Self::index(x, y, self.width)].set(cell_flags - CellFlags::MINE, value);
+    }
+
+    /// Changes the cell at index `i` with `f` and returns the `Change` for the
+    /// `History`. Pressed cells are only drawn and are not part of the history
+    fn change_cell<F>(&mut self, i: usize, f: F) -> Change
+    where
+        F: FnOnce(&mut CellFlags),
+    {
+        let before = self.cells[i];
+        f(&mut self.cells[i]);
+        (
+            i,
+            before - CellFlags::PRESSED,
+            self.cells[i] - CellFlags::PRESSED,
+        )
+    }
+
+    fn reveal_cell(cell: &mut CellFlags) {
+        cell.insert(CellFlags::REVEALED);
+        cell.remove(CellFlags::QUESTION);
     }
 
     fn filter_neighbors<F>(&self, x: u32, y: u32, f: F) -> Vec<Point<u32>>
     where
         F: Fn(CellFlags) -> bool,
     {
-        let neighbors = self.neighbors(x, y);
-        neighbors
-            .iter()
+        self.iter_neighbors(x, y)
             .filter(|p| f(self.cell(p.x, p.y)))
-            .cloned()
             .collect()
     }
 
+    fn count_adjacent(&self, i: usize, cell_flags: CellFlags) -> usize {
+        self.neighbor_indices(i)
+            .filter(|j| self.cells[*j].contains(cell_flags))
+            .count()
+    }
+
+    /// Returns an iterator over the indices of the cells adjacent to the cell
+    /// at index `i`, without allocating
+    fn neighbor_indices(&self, i: usize) -> impl Iterator<Item = usize> {
+        let (w, h) = (self.width as i64, self.height as i64);
+        let (x, y) = ((i % self.width) as i64, (i / self.width) as i64);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(move |(nx, ny)| (*nx, *ny) != (x, y))
+            .filter(move |(nx, ny)| *nx >= 0 && *ny >= 0 && *nx < w && *ny < h)
+            .map(move |(nx, ny)| (ny * w + nx) as usize)
+    }
+
     fn index(x: u32, y: u32, w: usize) -> usize {
         y as usize * w + x as usize
     }
 
-    fn make_cells(num_cells: usize, mine_indices: &[usize]) -> Vec<CellFlags> {
-        let mut cells = vec![CellFlags::default(); num_cells];
-        for idx in mine_indices {
-            cells[*idx].insert(CellFlags::MINE);
+    fn point(&self, i: usize) -> Point<u32> {
+        point!((i % self.width) as u32, (i / self.width) as u32)
+    }
+
+    fn place_mines<R: Rng>(&mut self, rng: &mut R, safe_indices: &[usize]) {
+        let candidates = (0..self.cells.len())
+            .filter(|i| !safe_indices.contains(i))
+            .collect::<Vec<_>>();
+
+        // sample `u32` indices so the layout does not depend on the platform's
+        // pointer width
+        let mine_indices =
+            math::gen_rand_unique::<u32, _, _>(rng, self.num_mines, 0, candidates.len() as u32);
+
+        for i in mine_indices {
+            self.cells[candidates[i as usize]].insert(CellFlags::MINE);
         }
-        cells
+
+        self.count_mines();
+        self.is_generated = true;
+    }
+
+    /// Counts the mines adjacent to every cell from scratch
+    fn count_mines(&mut self) {
+        let counts = (0..self.cells.len())
+            .map(|i| self.count_adjacent(i, CellFlags::MINE) as u8)
+            .collect();
+        self.adjacent_mines = counts;
     }
 }
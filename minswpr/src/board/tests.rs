@@ -1,4 +1,4 @@
-use super::{Board, CellFlags};
+use super::{Action, Board, CellFlags};
 use crate::math::Point;
 use std::collections::HashSet;
 
@@ -63,27 +63,379 @@ fn test_reveal_area() {
     let mut b = Board::new(9, 9, 0).unwrap();
     assert_eq!(0, b.reveal_area(0, 0).len());
 
-    b.cell_mut(1, 0).insert(CellFlags::MINE);
+    b.insert_flags(1, 0, CellFlags::MINE);
     assert_eq!(0, b.reveal_area(0, 0).len());
 
-    b.cell_mut(1, 0).insert(CellFlags::FLAG);
+    b.insert_flags(1, 0, CellFlags::FLAG);
     assert_eq!(0, b.reveal_area(0, 0).len());
 
-    b.cell_mut(0, 0).insert(CellFlags::REVEALED);
+    b.insert_flags(0, 0, CellFlags::REVEALED);
     assert_eq!(2, b.reveal_area(0, 0).len());
 
     b = Board::new(9, 9, 0).unwrap();
-    b.cell_mut(0, 0).insert(CellFlags::MINE | CellFlags::FLAG);
-    b.cell_mut(1, 0).insert(CellFlags::MINE | CellFlags::FLAG);
-    b.cell_mut(1, 1).insert(CellFlags::REVEALED);
+    b.insert_flags(0, 0, CellFlags::MINE | CellFlags::FLAG);
+    b.insert_flags(1, 0, CellFlags::MINE | CellFlags::FLAG);
+    b.insert_flags(1, 1, CellFlags::REVEALED);
 
     let mut b2 = b.clone();
 
     assert_eq!(6, b.reveal_area(1, 1).len());
 
-    b2.cell_mut(0, 0).remove(CellFlags::FLAG);
+    b2.remove_flags(0, 0, CellFlags::FLAG);
     assert_eq!(0, b2.reveal_area(1, 1).len());
 
-    b2.cell_mut(0, 1).insert(CellFlags::FLAG);
+    b2.insert_flags(0, 1, CellFlags::FLAG);
     assert_eq!(6, b2.reveal_area(1, 1).len());
 }
+
+#[test]
+fn test_board_new_deferred() {
+    let b = Board::new_deferred(9, 9, 10, 0).unwrap();
+    assert!(!b.is_generated());
+    assert!(b.cells().iter().all(|c| !c.contains(CellFlags::MINE)));
+
+    assert!(Board::new_deferred(0, 9, 0, 0).is_err());
+    assert!(Board::new_deferred(9, 0, 0, 0).is_err());
+    assert!(Board::new_deferred(usize::MAX, 2, 0, 0).is_err());
+    assert!(Board::new_deferred(Board::MAX_CELLS + 1, 1, 0, 0).is_err());
+}
+
+#[test]
+fn test_max_no_guess_attempts() {
+    let attempts = |w, h| {
+        Board::new_deferred(w, h, 0, 0)
+            .unwrap()
+            .max_no_guess_attempts()
+    };
+    assert!(attempts(9, 9) > attempts(30, 16));
+    assert_eq!(1, attempts(1_000, 1_000));
+}
+
+#[test]
+fn test_generate() {
+    for seed in 0..100 {
+        let mut b = Board::new_deferred(9, 9, 10, seed).unwrap();
+        b.generate(0, 0, false, false);
+        assert!(b.is_generated());
+        assert!(!b.cell(0, 0).contains(CellFlags::MINE));
+        assert_eq!(
+            10,
+            b.cells()
+                .iter()
+                .filter(|c| c.contains(CellFlags::MINE))
+                .count()
+        );
+    }
+}
+
+#[test]
+fn test_generate_safe_area() {
+    for seed in 0..100 {
+        let mut b = Board::new_deferred(9, 9, 72, seed).unwrap();
+        b.generate(4, 4, true, false);
+        assert!(!b.cell(4, 4).contains(CellFlags::MINE));
+        assert_eq!(0, b.count_adjacent_mines(4, 4));
+    }
+
+    // not enough room to keep the neighbors clear
+    let mut b = Board::new_deferred(9, 9, 80, 0).unwrap();
+    b.generate(4, 4, true, false);
+    assert!(!b.cell(4, 4).contains(CellFlags::MINE));
+    assert_eq!(8, b.count_adjacent_mines(4, 4));
+}
+
+fn mine_positions(b: &Board) -> Vec<usize> {
+    b.cells()
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.contains(CellFlags::MINE))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[test]
+fn test_with_seed() {
+    let b1 = Board::with_seed(30, 16, 99, 1234).unwrap();
+    let b2 = Board::with_seed(30, 16, 99, 1234).unwrap();
+    let b3 = Board::with_seed(30, 16, 99, 4321).unwrap();
+    assert_eq!(1234, b1.seed());
+    assert_eq!(mine_positions(&b1), mine_positions(&b2));
+    assert_ne!(mine_positions(&b1), mine_positions(&b3));
+
+    let mut b4 = Board::new_deferred(30, 16, 99, 1234).unwrap();
+    let mut b5 = Board::new_deferred(30, 16, 99, 1234).unwrap();
+    b4.generate(3, 3, true, false);
+    b5.generate(3, 3, true, false);
+    assert_eq!(mine_positions(&b4), mine_positions(&b5));
+}
+
+#[test]
+fn test_with_seed_layout() {
+    // guards against changes to the generator silently changing shared seeds
+    let b = Board::with_seed(9, 9, 10, 42).unwrap();
+    assert_eq!(
+        vec![12, 13, 18, 23, 25, 34, 52, 55, 62, 65],
+        mine_positions(&b)
+    );
+}
+
+#[test]
+fn test_from_str() {
+    let b: Board = "
+        .*F
+        f32
+        !..
+    "
+    .parse()
+    .unwrap();
+    assert_eq!((3, 3, 3), (b.width(), b.height(), b.num_mines()));
+    assert!(b.is_generated());
+    assert_eq!(CellFlags::MINE, b.cell(1, 0));
+    assert_eq!(CellFlags::MINE | CellFlags::FLAG, b.cell(2, 0));
+    assert_eq!(CellFlags::FLAG, b.cell(0, 1));
+    assert_eq!(CellFlags::REVEALED, b.cell(2, 1));
+    assert_eq!(CellFlags::MINE | CellFlags::REVEALED, b.cell(0, 2));
+    assert_eq!(CellFlags::empty(), b.cell(2, 2));
+}
+
+#[test]
+fn test_from_str_errors() {
+    assert!("".parse::<Board>().is_err());
+    assert!("..\n.".parse::<Board>().is_err());
+    assert!(".?".parse::<Board>().is_err());
+    assert!("*1.".parse::<Board>().is_ok());
+    assert!("*2.".parse::<Board>().is_err());
+}
+
+#[test]
+fn test_display_round_trip() {
+    for seed in 0..20 {
+        let mut b = Board::new_deferred(16, 16, 40, seed).unwrap();
+        b.generate(8, 8, true, false);
+        b.reveal_from(8, 8);
+        b.toggle_flag(0, 0);
+        b.toggle_flag(15, 15);
+
+        let text = b.to_string();
+        let parsed: Board = text.parse().unwrap();
+        assert_eq!(text, parsed.to_string());
+        assert_eq!(b.cells(), parsed.cells());
+        assert_eq!(b.num_mines(), parsed.num_mines());
+    }
+}
+
+#[test]
+fn test_reveal_from_text() {
+    let mut b: Board = "
+        ....*
+        .....
+        *....
+    "
+    .parse()
+    .unwrap();
+    b.reveal_from(4, 2);
+    assert_eq!("0001*\n11011\n*1000", b.to_string());
+}
+
+#[test]
+fn test_question_marks() {
+    let mut b: Board = "*..".parse().unwrap();
+
+    // flags are toggled when question marks are disabled
+    b.toggle_flag(0, 0);
+    assert_eq!("F..", b.to_string());
+    b.toggle_flag(0, 0);
+    assert_eq!("*..", b.to_string());
+
+    b.set_question_marks(true);
+    b.toggle_flag(0, 0);
+    assert_eq!("F..", b.to_string());
+    assert_eq!(1, b.count_flags());
+    b.toggle_flag(0, 0);
+    assert_eq!("Q..", b.to_string());
+    assert_eq!(0, b.count_flags());
+    b.toggle_flag(0, 0);
+    assert_eq!("*..", b.to_string());
+
+    // question marks are revealed like any other cell
+    b.toggle_flag(2, 0);
+    b.toggle_flag(2, 0);
+    assert_eq!("*.q", b.to_string());
+    b.reveal_from(2, 0);
+    assert_eq!("*10", b.to_string());
+    assert_eq!(CellFlags::REVEALED, b.cell(2, 0));
+
+    let parsed: Board = "Qq.".parse().unwrap();
+    assert_eq!(CellFlags::MINE | CellFlags::QUESTION, parsed.cell(0, 0));
+    assert_eq!(CellFlags::QUESTION, parsed.cell(1, 0));
+    assert_eq!("Qq.", parsed.to_string());
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut b: Board = "
+        ....*
+        .....
+        *....
+    "
+    .parse()
+    .unwrap();
+    let start = b.to_string();
+
+    assert_eq!(
+        vec![point!(0, 2)],
+        b.apply(Action::ToggleFlag(point!(0, 2)))
+    );
+    assert_eq!(13, b.apply(Action::Reveal(point!(4, 2))).len());
+    assert!(b.apply(Action::Reveal(point!(4, 2))).is_empty());
+    let end = b.to_string();
+    assert_eq!("0001*\n11011\nF1000", end);
+
+    assert_eq!(Some(Action::Reveal(point!(4, 2))), b.undo());
+    assert_eq!("....*\n.....\nF....", b.to_string());
+    assert_eq!(Some(Action::ToggleFlag(point!(0, 2))), b.undo());
+    assert_eq!(start, b.to_string());
+    assert_eq!(None, b.undo());
+
+    assert_eq!(Some(Action::ToggleFlag(point!(0, 2))), b.redo());
+    assert_eq!(Some(Action::Reveal(point!(4, 2))), b.redo());
+    assert_eq!(None, b.redo());
+    assert_eq!(end, b.to_string());
+}
+
+#[test]
+fn test_apply_clears_redo() {
+    let mut b: Board = "..*".parse().unwrap();
+    b.apply(Action::ToggleFlag(point!(2, 0)));
+    b.undo();
+    assert!(b.history().can_redo());
+
+    b.insert_flags(1, 0, CellFlags::PRESSED);
+    b.apply(Action::Reveal(point!(0, 0)));
+    assert!(!b.history().can_redo());
+    assert!(b.history().can_undo());
+
+    // pressed cells are left alone
+    b.undo();
+    assert_eq!(CellFlags::PRESSED, b.cell(1, 0));
+    assert!(!b.has_revealed_mine());
+}
+
+#[test]
+fn test_reveal_from_huge_board() {
+    // used to overflow the stack by recursing once per revealed cell
+    let mut b = Board::new(1000, 1000, 0).unwrap();
+    assert_eq!(1_000_000, b.reveal_from(500, 500));
+    assert!(b.remaining_cells().is_empty());
+
+    let mut b = Board::new_deferred(1000, 1000, 100, 0).unwrap();
+    b.generate(0, 0, true, false);
+    assert!(b.reveal_from(0, 0) > 1);
+    assert!(!b.has_revealed_mine());
+}
+
+fn assert_counts(b: &Board) {
+    for y in 0..b.height() as u32 {
+        for x in 0..b.width() as u32 {
+            let expected = b
+                .iter_neighbors(x, y)
+                .filter(|p| b.cell(p.x, p.y).contains(CellFlags::MINE))
+                .count();
+            assert_eq!(expected, b.count_adjacent_mines(x, y), "at ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn test_adjacent_mines_cache() {
+    for seed in 0..20 {
+        let mut b = Board::new_deferred(16, 16, 40, seed).unwrap();
+        assert_counts(&b);
+        b.generate(3, 3, true, seed % 2 == 0);
+        assert_counts(&b);
+
+        let json = serde_json::to_string(&b).unwrap();
+        assert_counts(&serde_json::from_str(&json).unwrap());
+
+        b.set_mine(0, 0, true);
+        b.set_mine(3, 3, true);
+        b.set_mine(3, 3, true);
+        assert_counts(&b);
+        b.set_mine(3, 3, false);
+        assert_counts(&b);
+
+        assert_counts(&b.to_string().parse().unwrap());
+
+        b.clear_all(CellFlags::MINE);
+        assert_counts(&b);
+    }
+}
+
+#[test]
+fn test_deserialize_checks_cells() {
+    let json =
+        r#"{"width":2,"height":2,"num_mines":0,"cells":[0,0,0],"is_generated":true,"seed":0}"#;
+    assert!(serde_json::from_str::<Board>(json).is_err());
+
+    // the dimensions overflow
+    let json = r#"{"width":18446744073709551615,"height":2,"num_mines":0,"cells":[0,0],
+        "is_generated":true,"seed":0}"#;
+    assert!(serde_json::from_str::<Board>(json).is_err());
+}
+
+#[test]
+fn test_deserialize_checks_mines() {
+    // two mines on a board that claims one
+    let json = r#"{"width":2,"height":1,"num_mines":1,"cells":[2,2],"is_generated":true,"seed":0}"#;
+    assert!(serde_json::from_str::<Board>(json).is_err());
+
+    // mines on a board whose mines are not placed yet
+    let json =
+        r#"{"width":2,"height":1,"num_mines":1,"cells":[2,0],"is_generated":false,"seed":0}"#;
+    assert!(serde_json::from_str::<Board>(json).is_err());
+
+    let json = r#"{"width":2,"height":1,"num_mines":1,"cells":[2,0],"is_generated":true,"seed":0}"#;
+    assert_eq!(1, serde_json::from_str::<Board>(json).unwrap().num_mines());
+}
+
+#[test]
+fn test_with_mines() {
+    let b = Board::with_mines(3, 2, &[0, 5], 7).unwrap();
+    assert!(b.is_generated());
+    assert_eq!(2, b.num_mines());
+    assert_eq!(7, b.seed());
+    assert_eq!("*..\n..*", b.to_string().trim());
+    assert_eq!(2, b.count_adjacent_mines(1, 0));
+
+    assert!(Board::with_mines(3, 2, &[6], 0).is_err());
+    assert!(Board::with_mines(3, 2, &[1, 1], 0).is_err());
+}
+
+#[test]
+fn test_bbbv() {
+    let b = Board::new_deferred(3, 3, 2, 0).unwrap();
+    assert_eq!(None, b.bbbv());
+
+    // two openings that clear every safe cell
+    let b: Board = "*..
+                    ...
+                    ..*"
+    .parse()
+    .unwrap();
+    assert_eq!(Some(2), b.bbbv());
+
+    // one opening and a number off its edge
+    let b: Board = "*.*
+                    ...
+                    ..."
+    .parse()
+    .unwrap();
+    assert_eq!(Some(2), b.bbbv());
+
+    // no openings
+    let b: Board = "*.
+                    .*"
+    .parse()
+    .unwrap();
+    assert_eq!(Some(2), b.bbbv());
+}
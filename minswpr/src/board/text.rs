@@ -0,0 +1,128 @@
+use super::{Board, CellFlags};
+use std::fmt;
+use std::str::FromStr;
+
+/// Writes the board as a grid with one character per cell:
+///
+/// | Character | Cell                                   |
+/// |-----------|----------------------------------------|
+/// | `.`       | hidden                                 |
+/// | `*`       | hidden mine                            |
+/// | `F`       | flagged mine                           |
+/// | `f`       | flagged cell without a mine            |
+/// | `Q`       | mine marked with a question mark       |
+/// | `q`       | question mark on a cell without a mine |
+/// | `0`-`8`   | revealed, with its adjacent mines      |
+/// | `!`       | revealed mine                          |
+///
+/// Boards that have not placed their mines yet are written without mines.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height as u32 {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for x in 0..self.width as u32 {
+                write!(f, "{}", self.cell_char(x, y))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a board in the format written by `Display`. Blank lines and
+/// whitespace around each row are ignored, every row must be the same length
+/// and revealed numbers must match the mines around them. The number of mines
+/// is the number of cells holding one.
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>();
+
+        let width = rows.first().map(|r| r.chars().count()).unwrap_or(0);
+        if width == 0 {
+            return Err("board must not be empty".to_string());
+        }
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        let mut hints = Vec::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("row {} must be {} cells wide", y, width));
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                cells.push(match c {
+                    '.' => CellFlags::empty(),
+                    '*' => CellFlags::MINE,
+                    'F' => CellFlags::MINE | CellFlags::FLAG,
+                    'f' => CellFlags::FLAG,
+                    'Q' => CellFlags::MINE | CellFlags::QUESTION,
+                    'q' => CellFlags::QUESTION,
+                    '!' => CellFlags::MINE | CellFlags::REVEALED,
+                    '0'..='8' => {
+                        hints.push((x as u32, y as u32, c.to_digit(10).unwrap() as usize));
+                        CellFlags::REVEALED
+                    }
+                    _ => return Err(format!("invalid cell `{}` at ({}, {})", c, x, y)),
+                });
+            }
+        }
+
+        let num_mines = cells.iter().filter(|c| c.contains(CellFlags::MINE)).count();
+        let mut board = Self::new_deferred(width, rows.len(), num_mines, 0)?;
+        board.cells = cells;
+        board.is_generated = true;
+        board.count_mines();
+
+        for (x, y, hint) in hints {
+            let adjacent_mines = board.count_adjacent_mines(x, y);
+            if hint != adjacent_mines {
+                return Err(format!(
+                    "cell ({}, {}) is revealed as {} but has {} adjacent mines",
+                    x, y, hint, adjacent_mines
+                ));
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+impl Board {
+    fn cell_char(&self, x: u32, y: u32) -> char {
+        let cell = self.cell(x, y);
+        let is_mine = cell.contains(CellFlags::MINE);
+
+        if cell.contains(CellFlags::REVEALED) {
+            if is_mine {
+                '!'
+            } else {
+                std::char::from_digit(self.count_adjacent_mines(x, y) as u32, 10).unwrap()
+            }
+        } else if cell.contains(CellFlags::FLAG) {
+            if is_mine {
+                'F'
+            } else {
+                'f'
+            }
+        } else if cell.contains(CellFlags::QUESTION) {
+            if is_mine {
+                'Q'
+            } else {
+                'q'
+            }
+        } else if is_mine {
+            '*'
+        } else {
+            '.'
+        }
+    }
+}
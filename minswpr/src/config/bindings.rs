@@ -0,0 +1,407 @@
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use serde::de::value;
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// An action that can be bound to keys in the `[keybindings]` section
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    /// Reveals the cell under the keyboard cursor
+    Reveal,
+    /// Flags or unflags the cell under the keyboard cursor
+    Flag,
+    /// Chords the cell under the keyboard cursor
+    Chord,
+    /// Starts a new game
+    Reset,
+    /// Highlights a cell to reveal next
+    Hint,
+    /// Undoes the last action on the board
+    Undo,
+    /// Redoes the last undone action on the board
+    Redo,
+    /// Saves the current game
+    Save,
+    /// Shows or hides the mine probability overlay
+    Heatmap,
+    /// Shows the lifetime statistics of the current board
+    Stats,
+    /// Pauses the game and hides the board until a key or mouse button is
+    /// pressed
+    Pause,
+    /// Saves the current game and exits
+    Quit,
+    /// Doubles the size of the window, or restores it if it is already doubled
+    Zoom,
+    /// Moves the keyboard cursor one cell to the left
+    CursorLeft,
+    /// Moves the keyboard cursor one cell to the right
+    CursorRight,
+    /// Moves the keyboard cursor one cell up
+    CursorUp,
+    /// Moves the keyboard cursor one cell down
+    CursorDown,
+}
+
+/// A key that can be bound in the `[keybindings]` section. Keys are kept apart
+/// from the keycodes of SDL2, so that the terminal front end can read the same
+/// bindings without SDL2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    /// A letter, digit or punctuation key. Letters are kept upper case.
+    Char(char),
+    /// A function key, from F1 to F12
+    F(u8),
+    Left,
+    Right,
+    Up,
+    Down,
+    Space,
+    Return,
+    Escape,
+    Backspace,
+    Tab,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Pause,
+}
+
+impl KeyCode {
+    /// The keys with a name, as they are written in the configuration file
+    const NAMED: [(&'static str, KeyCode); 16] = [
+        ("Left", KeyCode::Left),
+        ("Right", KeyCode::Right),
+        ("Up", KeyCode::Up),
+        ("Down", KeyCode::Down),
+        ("Space", KeyCode::Space),
+        ("Return", KeyCode::Return),
+        ("Escape", KeyCode::Escape),
+        ("Backspace", KeyCode::Backspace),
+        ("Tab", KeyCode::Tab),
+        ("Delete", KeyCode::Delete),
+        ("Insert", KeyCode::Insert),
+        ("Home", KeyCode::Home),
+        ("End", KeyCode::End),
+        ("PageUp", KeyCode::PageUp),
+        ("PageDown", KeyCode::PageDown),
+        ("Pause", KeyCode::Pause),
+    ];
+
+    /// Returns `Some(KeyCode)` of the specified SDL2 `keycode`, or `None` if it
+    /// can not be bound
+    pub fn from_sdl(keycode: Keycode) -> Option<Self> {
+        let code = match keycode {
+            Keycode::Left => Self::Left,
+            Keycode::Right => Self::Right,
+            Keycode::Up => Self::Up,
+            Keycode::Down => Self::Down,
+            Keycode::Space => Self::Space,
+            Keycode::Return | Keycode::KpEnter => Self::Return,
+            Keycode::Escape => Self::Escape,
+            Keycode::Backspace => Self::Backspace,
+            Keycode::Tab => Self::Tab,
+            Keycode::Delete => Self::Delete,
+            Keycode::Insert => Self::Insert,
+            Keycode::Home => Self::Home,
+            Keycode::End => Self::End,
+            Keycode::PageUp => Self::PageUp,
+            Keycode::PageDown => Self::PageDown,
+            Keycode::Pause => Self::Pause,
+            Keycode::F1 => Self::F(1),
+            Keycode::F2 => Self::F(2),
+            Keycode::F3 => Self::F(3),
+            Keycode::F4 => Self::F(4),
+            Keycode::F5 => Self::F(5),
+            Keycode::F6 => Self::F(6),
+            Keycode::F7 => Self::F(7),
+            Keycode::F8 => Self::F(8),
+            Keycode::F9 => Self::F(9),
+            Keycode::F10 => Self::F(10),
+            Keycode::F11 => Self::F(11),
+            Keycode::F12 => Self::F(12),
+            // the keycodes of printable keys are their lower case characters
+            k => match k as i32 {
+                c @ 0x21..=0x7e => Self::Char((c as u8 as char).to_ascii_uppercase()),
+                _ => return None,
+            },
+        };
+        Some(code)
+    }
+}
+
+impl fmt::Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Char(c) => write!(f, "{}", c),
+            Self::F(n) => write!(f, "F{}", n),
+            code => {
+                let (name, _) = Self::NAMED.iter().find(|(_, c)| c == code).unwrap();
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+impl FromStr for KeyCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, code)) = Self::NAMED.iter().find(|(n, _)| n.eq_ignore_ascii_case(s)) {
+            return Ok(*code);
+        }
+
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_graphic() => Ok(Self::Char(c.to_ascii_uppercase())),
+            _ => match s.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=12).contains(&n) => Ok(Self::F(n)),
+                _ => Err(format!("unknown key `{}`", s)),
+            },
+        }
+    }
+}
+
+/// A key, optionally pressed together with Ctrl. Keys are written as named by
+/// `KeyCode`, like `"Space"`, `"F2"` or `"A"`, with a `"Ctrl+"` prefix if Ctrl
+/// must be held
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl Key {
+    /// Returns `Some(Key)` of a `KeyDown` event with the specified SDL2
+    /// `keycode` and `keymod`, or `None` if the key can not be bound
+    pub fn from_event(keycode: Keycode, keymod: Mod) -> Option<Self> {
+        KeyCode::from_sdl(keycode).map(|code| Self {
+            code,
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+        })
+    }
+}
+
+/// Writes the key the way it is read by `Key::from_str`
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        write!(f, "{}", self.code)
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, ctrl) = match s.strip_prefix("Ctrl+") {
+            Some(name) => (name, true),
+            None => (s, false),
+        };
+
+        name.parse::<KeyCode>()
+            .map(|code| Self { code, ctrl })
+            .map_err(|_| format!("unknown key `{}`", s))
+    }
+}
+
+/// The `Command` bound to each key. Commands are read as strings, since toml
+/// can not read table keys as enums
+#[derive(Deserialize, Clone, Debug)]
+#[serde(try_from = "HashMap<String, Vec<String>>")]
+pub struct KeyBindings {
+    commands: HashMap<Key, Command>,
+    keys: HashMap<Command, Vec<Key>>,
+}
+
+impl KeyBindings {
+    /// Returns `Some(Command)` bound to the specified `key`, `None` if it is
+    /// not bound
+    pub fn get(&self, key: Key) -> Option<Command> {
+        self.commands.get(&key).copied()
+    }
+
+    /// Returns the keys bound to the specified `command`, in the order they
+    /// are listed in the configuration file
+    pub fn keys(&self, command: Command) -> &[Key] {
+        self.keys
+            .get(&command)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl TryFrom<HashMap<String, Vec<String>>> for KeyBindings {
+    type Error = String;
+
+    fn try_from(bindings: HashMap<String, Vec<String>>) -> Result<Self, Self::Error> {
+        let mut commands = HashMap::new();
+        let mut keys = HashMap::new();
+
+        for (command, names) in bindings {
+            let command = Command::deserialize(command.as_str().into_deserializer())
+                .map_err(|e: value::Error| e.to_string())?;
+
+            for name in names {
+                let key = name.parse::<Key>()?;
+                if let Some(other) = commands.insert(key, command) {
+                    return Err(format!(
+                        "key `{}` is bound to both `{:?}` and `{:?}`",
+                        name, other, command
+                    ));
+                }
+                keys.entry(command).or_insert_with(Vec::new).push(key);
+            }
+        }
+
+        Ok(Self { commands, keys })
+    }
+}
+
+/// The mouse buttons that act on the clicked cell. Buttons are written as
+/// `"Left"`, `"Middle"`, `"Right"`, `"X1"` or `"X2"`, and can be swapped for
+/// left-handed play
+#[derive(Deserialize, Clone, Debug)]
+#[serde(try_from = "RawMouseConfig")]
+pub struct MouseConfig {
+    pub reveal: MouseButton,
+    pub flag: MouseButton,
+    pub chord: MouseButton,
+}
+
+#[derive(Deserialize)]
+struct RawMouseConfig {
+    reveal: String,
+    flag: String,
+    chord: String,
+}
+
+impl TryFrom<RawMouseConfig> for MouseConfig {
+    type Error = String;
+
+    fn try_from(raw: RawMouseConfig) -> Result<Self, Self::Error> {
+        let config = Self {
+            reveal: self::parse_mouse_button(&raw.reveal)?,
+            flag: self::parse_mouse_button(&raw.flag)?,
+            chord: self::parse_mouse_button(&raw.chord)?,
+        };
+
+        if config.reveal == config.flag
+            || config.reveal == config.chord
+            || config.flag == config.chord
+        {
+            return Err(
+                "reveal, flag and chord must be bound to different mouse buttons".to_string(),
+            );
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_mouse_button(name: &str) -> Result<MouseButton, String> {
+    match name {
+        "Left" => Ok(MouseButton::Left),
+        "Middle" => Ok(MouseButton::Middle),
+        "Right" => Ok(MouseButton::Right),
+        "X1" => Ok(MouseButton::X1),
+        "X2" => Ok(MouseButton::X2),
+        _ => Err(format!("unknown mouse button `{}`", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Key, KeyBindings, KeyCode, MouseConfig};
+    use sdl2::keyboard::{Keycode, Mod};
+    use sdl2::mouse::MouseButton;
+
+    #[test]
+    fn test_mouse_config() {
+        let config: MouseConfig = toml::from_str(
+            r#"
+            reveal = "Right"
+            flag = "Left"
+            chord = "Middle"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(MouseButton::Right, config.reveal);
+        assert_eq!(MouseButton::Left, config.flag);
+        assert_eq!(MouseButton::Middle, config.chord);
+    }
+
+    #[test]
+    fn test_mouse_config_errors() {
+        let read = |s| toml::from_str::<MouseConfig>(s).err().unwrap().to_string();
+
+        let err = read(
+            r#"
+            reveal = "Left"
+            flag = "Left"
+            chord = "Middle"
+            "#,
+        );
+        assert!(err.contains("different mouse buttons"), "{}", err);
+
+        let err = read(
+            r#"
+            reveal = "Left"
+            flag = "Back"
+            chord = "Middle"
+            "#,
+        );
+        assert!(err.contains("unknown mouse button `Back`"), "{}", err);
+    }
+
+    #[test]
+    fn test_key_bindings_keys() {
+        let bindings: KeyBindings = toml::from_str(r#"pause = ["Escape", "Ctrl+P"]"#).unwrap();
+
+        let keys = bindings
+            .keys(Command::Pause)
+            .iter()
+            .map(Key::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Escape", "Ctrl+P"], keys);
+        assert!(bindings.keys(Command::Quit).is_empty());
+    }
+
+    #[test]
+    fn test_key_names() {
+        for name in &["A", "1", "/", "F2", "F12", "Space", "Escape", "Ctrl+S"] {
+            assert_eq!(*name, name.parse::<Key>().unwrap().to_string());
+        }
+        assert_eq!("A", "a".parse::<Key>().unwrap().to_string());
+        assert!("F13".parse::<Key>().is_err());
+        assert!("Ctrl+Nothing".parse::<Key>().is_err());
+
+        let key = Key::from_event(Keycode::S, Mod::LCTRLMOD).unwrap();
+        assert_eq!("Ctrl+S".parse::<Key>().unwrap(), key);
+        assert_eq!(Some(KeyCode::F(2)), KeyCode::from_sdl(Keycode::F2));
+        assert_eq!(None, KeyCode::from_sdl(Keycode::LShift));
+    }
+
+    #[test]
+    fn test_key_bindings_commands() {
+        assert!(toml::from_str::<KeyBindings>("reveal = []\ncursor_left = []").is_ok());
+
+        let err = toml::from_str::<KeyBindings>("explode = []")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("unknown variant `explode`"), "{}", err);
+    }
+}
@@ -18,18 +18,34 @@ lazy_static! {
     };
 }
 
+/// The board settings of a difficulty
 #[derive(new)]
-struct DifficultyConfig {
-    dimen: Dimen<usize>,
-    num_mines: usize,
+pub struct DifficultyConfig {
+    pub dimen: Dimen<usize>,
+    pub num_mines: usize,
+}
+
+/// Returns the settings of the specified `difficulty` or returns an
+/// `Err(String)` if the difficulty was not found
+pub fn get(difficulty: &str) -> MsResult<&'static DifficultyConfig> {
+    CONFIGS
+        .get(difficulty)
+        .ok_or_else(|| format!("unknown difficulty: `{}`", difficulty))
+}
+
+/// Returns the name of the difficulty with the specified `dimen` and
+/// `num_mines`, or `None` if they do not match any difficulty
+pub fn find(dimen: Dimen<usize>, num_mines: usize) -> Option<&'static str> {
+    CONFIGS
+        .iter()
+        .find(|(_, d)| d.dimen == dimen && d.num_mines == num_mines)
+        .map(|(name, _)| *name)
 }
 
 /// Applies the settings of the specified `difficulty` to the specified `config`
 /// or returns and `Err(String)` if the the difficulty was not found
 pub fn apply_to_config(config: &mut Config, difficulty: &str) -> MsResult {
-    let d = CONFIGS
-        .get(difficulty)
-        .ok_or_else(|| format!("unknown difficulty: `{}`", difficulty))?;
+    let d = self::get(difficulty)?;
     config.board.dimen = d.dimen;
     config.board.num_mines = d.num_mines;
     Ok(())
@@ -1,11 +1,16 @@
+mod bindings;
 /// Pre-defined difficulties present in the original version
 pub mod difficulties;
 
+pub use self::bindings::*;
+
+use crate::control::TimeFormat;
 use crate::math::{self, Dimen};
 use crate::MsResult;
 use sdl2::pixels::Color;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Deserializer};
+use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
 use std::fs;
@@ -26,6 +31,10 @@ pub struct Config {
     pub control: ControlConfig,
     pub board: BoardConfig,
     pub layout: LayoutConfig,
+    pub keybindings: KeyBindings,
+    pub mouse: MouseConfig,
+    pub overlay: OverlayConfig,
+    pub replay: ReplayConfig,
 }
 
 /// Window specific values
@@ -35,6 +44,8 @@ pub struct WindowConfig {
     pub dimen: Dimen,
     #[serde(deserialize_with = "read_color")]
     pub bg_color: Color,
+    /// If true, the game is paused when the window loses focus
+    pub pause_on_focus_loss: bool,
 }
 
 /// `Layout` specific values
@@ -46,6 +57,26 @@ pub struct LayoutConfig {
     pub guides: bool,
 }
 
+/// Values specific to recording replays
+#[derive(Deserialize, Clone)]
+pub struct ReplayConfig {
+    /// If true, every finished game is written as a replay to the `replays`
+    /// directory in the user's data directory
+    pub record: bool,
+}
+
+/// Values specific to the messages drawn over the window, like the prompt for
+/// the name of a new high score
+#[derive(Deserialize, Clone)]
+pub struct OverlayConfig {
+    #[serde(deserialize_with = "read_color")]
+    pub color: Color,
+    /// The opacity of `color` over the window, from 0 to 255
+    pub alpha: u8,
+    #[serde(deserialize_with = "read_color")]
+    pub text_color: Color,
+}
+
 /// Values specific to the central control panel located on the top of the board
 /// (by default)
 #[derive(Deserialize, Clone)]
@@ -89,6 +120,25 @@ pub struct LedDisplayConfig {
     pub padding: u32,
     #[serde(deserialize_with = "read_color")]
     pub text_color: Color,
+    /// How the time is shown on the stopwatch, whole seconds if not set
+    #[serde(default)]
+    pub format: TimeFormat,
+}
+
+impl LedDisplayConfig {
+    /// The amount of characters that fit in a display of the configured
+    /// `dimen`, the three digits of the original game
+    const DIGITS: usize = 3;
+
+    /// Returns the dimensions of the display. The configured `dimen` fits
+    /// three digits, and is widened to fit longer times in `format`
+    pub fn fitted_dimen(&self) -> Dimen {
+        let len = cmp::max(Self::DIGITS, self.format.max_len());
+        Dimen::new(
+            self.dimen.width() * len as u32 / Self::DIGITS as u32,
+            self.dimen.height(),
+        )
+    }
 }
 
 /// `Board` specific values
@@ -96,6 +146,18 @@ pub struct LedDisplayConfig {
 pub struct BoardConfig {
     pub dimen: Dimen<usize>,
     pub num_mines: usize,
+    /// If true, the neighbors of the first revealed cell are kept free of mines
+    /// as well as the cell itself
+    pub safe_area: bool,
+    /// If true, boards are generated so that they can be cleared from the first
+    /// revealed cell without guessing, as far as `Board::generate` finds one
+    pub no_guess: bool,
+    /// The seed used to place the mines. A random seed is used for every board
+    /// if not specified
+    pub seed: Option<u64>,
+    /// If true, flagging a flagged cell marks it with a question mark before
+    /// clearing it
+    pub question_marks: bool,
     pub cells: CellConfig,
 }
 
@@ -107,6 +169,8 @@ pub struct CellConfig {
     pub color: Color,
     #[serde(deserialize_with = "read_color")]
     pub pressed_color: Color,
+    #[serde(deserialize_with = "read_color")]
+    pub hint_color: Color,
     pub border_width: u32,
     #[serde(deserialize_with = "read_color")]
     pub border_color: Color,
@@ -116,6 +180,9 @@ pub struct CellConfig {
     pub text_color: Color,
     pub mines: MinesConfig,
     pub flags: FlagsConfig,
+    pub question: QuestionConfig,
+    pub heatmap: HeatmapConfig,
+    pub cursor: CursorConfig,
 }
 
 /// Values specific to the look of mines
@@ -136,6 +203,37 @@ pub struct FlagsConfig {
     pub dimen: Dimen,
 }
 
+/// Values specific to the look of question marks
+#[derive(Deserialize, Clone)]
+pub struct QuestionConfig {
+    #[serde(deserialize_with = "read_color")]
+    pub color: Color,
+    /// The text drawn on cells marked with a question mark
+    pub glyph: String,
+}
+
+/// Values specific to the mine probability overlay drawn on hidden cells
+#[derive(Deserialize, Clone)]
+pub struct HeatmapConfig {
+    /// The color of cells that are certainly safe
+    #[serde(deserialize_with = "read_color")]
+    pub safe_color: Color,
+    /// The color of cells that certainly contain a mine
+    #[serde(deserialize_with = "read_color")]
+    pub mine_color: Color,
+    /// The opacity of the overlay
+    pub alpha: u8,
+}
+
+/// Values specific to the look of the keyboard cursor
+#[derive(Deserialize, Clone)]
+pub struct CursorConfig {
+    #[serde(deserialize_with = "read_color")]
+    pub color: Color,
+    /// The width of the outline drawn around the cell under the cursor
+    pub width: u32,
+}
+
 /// Values specific for fonts
 #[derive(Deserialize, Clone)]
 pub struct FontConfig {
@@ -1,44 +1,190 @@
+use crate::math::{Dimen, Point};
+use crate::record::GameRecord;
+use crate::scores;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use std::cmp;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 
+lazy_static! {
+    static ref EPOCH: Instant = Instant::now();
+}
+
+/// Where a `Stopwatch` reads the current time from
+#[derive(Clone, Debug)]
+pub enum Clock {
+    /// The system clock
+    System,
+    /// A `ManualClock` that only moves when it is set, like to the timestamps
+    /// of a replay
+    Manual(ManualClock),
+}
+
+impl Clock {
+    /// Returns the current time of this clock, counted from a point that stays
+    /// the same while the program runs
+    pub fn now(&self) -> Duration {
+        match self {
+            Self::System => EPOCH.elapsed(),
+            Self::Manual(c) => c.now(),
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::System
+    }
+}
+
+/// A clock whose time is set by hand. Clones share the same time
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl ManualClock {
+    /// Returns the current time of this clock
+    pub fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    /// Sets the current time of this clock
+    pub fn set(&self, now: Duration) {
+        self.now.set(now)
+    }
+}
+
 /// Tracks the elapsed time during an active game
-#[derive(new, Default)]
+#[derive(new, Default, Clone)]
 pub struct Stopwatch {
     #[new(default)]
-    instant: Option<Instant>,
+    clock: Clock,
+    #[new(default)]
+    started: Option<Duration>,
     #[new(default)]
     elapsed_final: Duration,
 }
 
 impl Stopwatch {
+    /// Creates a new `Stopwatch` that reads the time from the specified `clock`
+    pub fn with_clock(clock: Clock) -> Self {
+        Self {
+            clock,
+            ..Default::default()
+        }
+    }
+
     /// Starts this `Stopwatch`
     pub fn start(&mut self) {
-        *self = Self {
-            instant: Some(Instant::now()),
-            elapsed_final: Default::default(),
-        };
+        self.started = Some(self.clock.now());
+        self.elapsed_final = Default::default();
     }
 
-    /// Sets `elapsed_final` to the current elapsed time and sets `instant` to
+    /// Sets `elapsed_final` to the current elapsed time and sets `started` to
     /// `None`. If `Stopwatch::elapsed` is called after this, the elapsed
     /// duration at the time of this call will be returned until
     /// `Stopwatch::reset` is called
     pub fn stop(&mut self) {
         self.elapsed_final = self.elapsed();
-        self.instant = None;
+        self.started = None;
     }
 
-    /// Resets this stopwatch
+    /// Starts this `Stopwatch` again after `Stopwatch::stop` was called,
+    /// counting on from the elapsed time at the time of stopping. This method
+    /// has no effect if this `Stopwatch` is running
+    pub fn resume(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(self.clock.now());
+        }
+    }
+
+    /// Returns true if this `Stopwatch` has been started and is not stopped
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Resets this stopwatch, keeping its `Clock`
     pub fn reset(&mut self) {
-        *self = Self::default();
+        *self = Self::with_clock(self.clock.clone());
     }
 
     /// Returns the elapsed `Duration` since `Stopwatch::start` was called. If
     /// this `Stopwatch` has been stopped with `Stopwatch::stop`, the elapsed
     /// duration at time of stopping is returned
     pub fn elapsed(&self) -> Duration {
-        self.instant
-            .map(|i| i.elapsed())
-            .unwrap_or_else(|| self.elapsed_final)
+        let now = self.clock.now();
+        self.elapsed_final
+            + self
+                .started
+                .map(|s| now.checked_sub(s).unwrap_or_default())
+                .unwrap_or_default()
+    }
+}
+
+/// A `Stopwatch` is saved as its elapsed time and restored stopped
+impl Serialize for Stopwatch {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        self.elapsed().serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for Stopwatch {
+    fn deserialize<D: Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            clock: Clock::System,
+            started: None,
+            elapsed_final: Duration::deserialize(des)?,
+        })
+    }
+}
+
+/// How a `Stopwatch` time is displayed, set by the `format` of the stopwatch in
+/// `[control.stopwatch]`
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// Whole seconds up to 999, like the original game
+    Seconds,
+    /// Seconds with tenths, like `"12.3"`, up to 999.9
+    Tenths,
+    /// Minutes, seconds and milliseconds, like `"01:02.345"`, up to 99:59.999
+    Millis,
+}
+
+impl TimeFormat {
+    /// Returns the specified `elapsed` time as text in this format. Times are
+    /// truncated rather than rounded, and stop at the largest time that fits
+    pub fn format(self, elapsed: Duration) -> String {
+        let millis = elapsed.as_millis();
+        match self {
+            Self::Seconds => cmp::min(millis / 1000, 999).to_string(),
+            Self::Tenths => {
+                let tenths = cmp::min(millis / 100, 9999);
+                format!("{}.{}", tenths / 10, tenths % 10)
+            }
+            Self::Millis => {
+                let millis = cmp::min(millis, 100 * 60 * 1000 - 1);
+                format!(
+                    "{:02}:{:02}.{:03}",
+                    millis / 60_000,
+                    millis / 1000 % 60,
+                    millis % 1000
+                )
+            }
+        }
+    }
+
+    /// Returns the amount of characters in the longest text in this format
+    pub fn max_len(self) -> usize {
+        self.format(Duration::from_secs(u32::MAX.into())).len()
+    }
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        Self::Seconds
     }
 }
 
@@ -77,3 +223,289 @@ impl Button {
         self.is_released = is_released
     }
 }
+
+/// Manages the keyboard cursor on the board. The cursor is hidden until it is
+/// first moved with the keyboard
+#[derive(new, Default)]
+pub struct Cursor {
+    #[new(default)]
+    pos: Option<Point<u32>>,
+}
+
+impl Cursor {
+    /// Returns `Some(Point<u32>)` with the board position of the cursor if it
+    /// is visible, `None` otherwise
+    pub fn pos(&self) -> Option<Point<u32>> {
+        self.pos
+    }
+
+    /// Sets the board position of the cursor, or hides it if `pos` is `None`
+    pub fn set_pos(&mut self, pos: Option<Point<u32>>) {
+        self.pos = pos
+    }
+
+    /// Moves the cursor by the specified amount of cells, staying within a
+    /// board of the specified `dimen`. If the cursor is hidden, it is shown in
+    /// the top left corner instead
+    pub fn move_by(&mut self, dx: i32, dy: i32, dimen: Dimen<usize>) {
+        let clamp = |v: i32, max: usize| cmp::max(0, cmp::min(v, max as i32 - 1)) as u32;
+        self.pos = Some(match self.pos {
+            Some(p) => point!(
+                clamp(p.x as i32 + dx, dimen.x),
+                clamp(p.y as i32 + dy, dimen.y)
+            ),
+            None => point!(0, 0),
+        });
+    }
+}
+
+/// What releasing the reveal or flag mouse button over the board should do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Release {
+    /// Act on the cell like the released button does on its own
+    Click,
+    /// Chord the cell, because both buttons were held
+    Chord,
+    /// Do nothing, because the button was not pressed over the board or the
+    /// other button of a chord was already released
+    Ignore,
+}
+
+/// Tracks the reveal and flag mouse buttons held over the board, so that
+/// pressing both together chords the cell like the chord button does
+#[derive(new, Default)]
+pub struct MouseChord {
+    #[new(default)]
+    reveal_held: bool,
+    #[new(default)]
+    flag_held: bool,
+    #[new(default)]
+    is_chording: bool,
+}
+
+impl MouseChord {
+    /// Returns true if a chord was started and has not been finished by
+    /// releasing both buttons
+    pub fn is_chording(&self) -> bool {
+        self.is_chording
+    }
+
+    /// Returns true if both buttons are held
+    pub fn is_both_held(&self) -> bool {
+        self.reveal_held && self.flag_held
+    }
+
+    /// Records that the reveal button, or the flag button if `is_reveal` is
+    /// false, was pressed. Returns true if both buttons are now held, which
+    /// starts a chord
+    pub fn press(&mut self, is_reveal: bool) -> bool {
+        if is_reveal {
+            self.reveal_held = true;
+        } else {
+            self.flag_held = true;
+        }
+
+        if self.is_both_held() {
+            self.is_chording = true;
+        }
+        self.is_chording
+    }
+
+    /// Records that the reveal button, or the flag button if `is_reveal` is
+    /// false, was released and returns what the release should do. Only the
+    /// first button released during a chord performs it
+    pub fn release(&mut self, is_reveal: bool) -> Release {
+        let was_held = if is_reveal {
+            self.reveal_held
+        } else {
+            self.flag_held
+        };
+        let was_both_held = self.is_both_held();
+
+        if is_reveal {
+            self.reveal_held = false;
+        } else {
+            self.flag_held = false;
+        }
+
+        if self.is_chording {
+            if !self.reveal_held && !self.flag_held {
+                self.is_chording = false;
+            }
+
+            if was_both_held {
+                Release::Chord
+            } else {
+                Release::Ignore
+            }
+        } else if was_held {
+            Release::Click
+        } else {
+            Release::Ignore
+        }
+    }
+
+    /// Updates which buttons are held from the current mouse state, in case
+    /// they were released outside of the board
+    pub fn sync(&mut self, reveal_held: bool, flag_held: bool) {
+        self.reveal_held &= reveal_held;
+        self.flag_held &= flag_held;
+        if !self.reveal_held && !self.flag_held {
+            self.is_chording = false;
+        }
+    }
+}
+
+/// The name being typed for a new high score after winning a game
+#[derive(new, Debug, Clone)]
+pub struct NameEntry {
+    record: GameRecord,
+    rank: usize,
+    #[new(default)]
+    name: String,
+}
+
+impl NameEntry {
+    /// Returns the `GameRecord` of the won game
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    /// Returns the zero-based rank the score will take in the high score table
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// Returns the name typed so far
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Appends the specified `text` to the name, up to `scores::MAX_NAME_LEN`
+    /// characters
+    pub fn push_str(&mut self, text: &str) {
+        let len = self.name.chars().count();
+        self.name
+            .extend(text.chars().take(scores::MAX_NAME_LEN.saturating_sub(len)));
+    }
+
+    /// Removes the last character of the name
+    pub fn pop(&mut self) {
+        self.name.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Clock, Cursor, ManualClock, MouseChord, NameEntry, Release, Stopwatch, TimeFormat,
+    };
+    use crate::math::Point;
+    use crate::record::GameRecord;
+    use crate::scores;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stopwatch_pause() {
+        let clock = ManualClock::default();
+        let mut stopwatch = Stopwatch::with_clock(Clock::Manual(clock.clone()));
+        assert!(!stopwatch.is_running());
+
+        clock.set(Duration::from_secs(2));
+        stopwatch.start();
+        clock.set(Duration::from_millis(3_500));
+        assert_eq!(Duration::from_millis(1_500), stopwatch.elapsed());
+        stopwatch.stop();
+        assert!(!stopwatch.is_running());
+
+        // time passing while stopped is not counted
+        clock.set(Duration::from_secs(10));
+        assert_eq!(Duration::from_millis(1_500), stopwatch.elapsed());
+
+        stopwatch.resume();
+        assert!(stopwatch.is_running());
+        clock.set(Duration::from_secs(11));
+        assert_eq!(Duration::from_millis(2_500), stopwatch.elapsed());
+
+        // the clock is kept when the stopwatch is reset
+        stopwatch.reset();
+        stopwatch.start();
+        clock.set(Duration::from_secs(12));
+        assert_eq!(Duration::from_secs(1), stopwatch.elapsed());
+    }
+
+    #[test]
+    fn test_cursor_move_by() {
+        let mut cursor = Cursor::new();
+        assert_eq!(None, cursor.pos());
+
+        cursor.move_by(1, 0, point!(3, 2));
+        assert_eq!(Some(point!(0, 0)), cursor.pos());
+
+        cursor.move_by(1, 1, point!(3, 2));
+        assert_eq!(Some(point!(1, 1)), cursor.pos());
+
+        cursor.move_by(5, 5, point!(3, 2));
+        assert_eq!(Some(point!(2, 1)), cursor.pos());
+
+        cursor.move_by(-5, -5, point!(3, 2));
+        assert_eq!(Some(point!(0, 0)), cursor.pos());
+    }
+
+    #[test]
+    fn test_mouse_chord() {
+        let mut chord = MouseChord::new();
+        assert!(!chord.press(true));
+        assert_eq!(Release::Click, chord.release(true));
+
+        // releasing a button that was not pressed over the board
+        assert_eq!(Release::Ignore, chord.release(false));
+
+        // the first release of a chord performs it and the second is ignored
+        assert!(!chord.press(false));
+        assert!(chord.press(true));
+        assert_eq!(Release::Chord, chord.release(false));
+        assert!(chord.is_chording());
+        assert_eq!(Release::Ignore, chord.release(true));
+        assert!(!chord.is_chording());
+
+        // buttons released outside of the board end the chord
+        chord.press(true);
+        chord.press(false);
+        chord.sync(false, false);
+        assert!(!chord.is_chording());
+        assert_eq!(Release::Ignore, chord.release(true));
+    }
+
+    #[test]
+    fn test_name_entry() {
+        let record = GameRecord::new_test(true, 9, 9, 10, Duration::default());
+        let mut entry = NameEntry::new(record, 0);
+        entry.push_str("ab");
+        entry.pop();
+        assert_eq!("a", entry.name());
+
+        entry.push_str(&"x".repeat(scores::MAX_NAME_LEN));
+        assert_eq!(scores::MAX_NAME_LEN, entry.name().chars().count());
+        assert!(entry.name().starts_with('a'));
+    }
+
+    #[test]
+    fn test_time_format() {
+        let t = Duration::from_millis(62_345);
+        assert_eq!("62", TimeFormat::Seconds.format(t));
+        assert_eq!("62.3", TimeFormat::Tenths.format(t));
+        assert_eq!("01:02.345", TimeFormat::Millis.format(t));
+
+        assert_eq!("0", TimeFormat::Seconds.format(Duration::from_millis(999)));
+        assert_eq!("0.0", TimeFormat::Tenths.format(Duration::default()));
+
+        let t = Duration::from_secs(10_000);
+        assert_eq!("999", TimeFormat::Seconds.format(t));
+        assert_eq!("999.9", TimeFormat::Tenths.format(t));
+        assert_eq!("99:59.999", TimeFormat::Millis.format(t));
+
+        assert_eq!(3, TimeFormat::Seconds.max_len());
+        assert_eq!(9, TimeFormat::Millis.max_len());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::MsResult;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Returns the path of the file or directory with the specified `name` in the
+/// minswpr directory of the user's data directory, or an `Err(String)` if the
+/// data directory could not be determined
+pub fn path(name: &str) -> MsResult<PathBuf> {
+    dirs::data_dir()
+        .map(|d| d.join("minswpr").join(name))
+        .ok_or_else(|| "could not determine the data directory".to_string())
+}
+
+/// Writes the specified `value` as JSON to the file at `path`, creating its
+/// parent directories if needed. The JSON is indented if `pretty` is true, for
+/// files that are meant to be read by the player as well.
+pub fn write<T: Serialize>(value: &T, path: &Path, pretty: bool) -> MsResult {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+    .map_err(|e| e.to_string())?;
+
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// Reads the JSON file at `path`. Returns `Ok(None)` if there is no such file
+/// or an `Err(String)` naming the file as the specified `kind` of file if it
+/// could not be read.
+pub fn read<T: DeserializeOwned>(path: &Path, kind: &str) -> MsResult<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {} `{}`: {}", kind, path.display(), e))?;
+
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("corrupt {} `{}`: {}", kind, path.display(), e))
+}
+
+/// A path in the temporary directory for tests that write files, removed
+/// again when dropped so that a failed assertion does not leave it behind
+#[cfg(test)]
+pub(crate) struct TempFile {
+    path: PathBuf,
+}
+
+#[cfg(test)]
+impl TempFile {
+    /// Creates a new `TempFile` whose name includes the specified `name`,
+    /// which must be unique among the tests run at the same time
+    pub fn new(name: &str) -> Self {
+        let file_name = format!("minswpr-{}-{}.json", name, std::process::id());
+        Self {
+            path: std::env::temp_dir().join(file_name),
+        }
+    }
+
+    /// Returns the path of the file, which does not exist until it is written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Writes the specified `value` to a `TempFile` with `write` and returns what
+/// `read` reads back from it, for testing the files of the data directory
+#[cfg(test)]
+pub(crate) fn round_trip<T, U>(
+    name: &str,
+    value: &T,
+    write: impl Fn(&T, &Path) -> MsResult,
+    read: impl Fn(&Path) -> MsResult<U>,
+) -> MsResult<U> {
+    let file = TempFile::new(name);
+    write(value, file.path())?;
+    read(file.path())
+}
@@ -11,6 +11,7 @@ use sdl2::rect::Rect;
 pub(super) struct DrawCell<'a> {
     board: &'a Board,
     board_pos: Point<u32>,
+    is_cursor: bool,
     config: &'a CellConfig,
 }
 
@@ -18,9 +19,15 @@ impl DrawCell<'_> {
     pub fn draw(&mut self, ctx: &DrawContext, pos: Point) -> MsResult {
         let cell = self.board.cell(self.board_pos.x, self.board_pos.y);
         if cell.contains(CellFlags::REVEALED) {
-            self.draw_revealed(cell, ctx, pos)
+            self.draw_revealed(cell, ctx, pos)?;
         } else {
-            self.draw_hidden(cell, ctx, pos)
+            self.draw_hidden(cell, ctx, pos)?;
+        }
+
+        if self.is_cursor {
+            self.draw_cursor(ctx, pos)
+        } else {
+            Ok(())
         }
     }
 
@@ -52,13 +59,38 @@ impl DrawCell<'_> {
         if cell.contains(CellFlags::FLAG) {
             let flags = &self.config.flags;
             self.draw_centered_rect(&ctx, pos, flags.dimen, flags.color)
+        } else if cell.contains(CellFlags::QUESTION) {
+            let question = &self.config.question;
+            self.draw_centered_text(
+                ctx,
+                pos,
+                Text::new(&question.glyph, "board.cell", question.color),
+            )
         } else if cell.contains(CellFlags::PRESSED) {
             draw_rect!(self.config.dimen, self.config.pressed_color, ctx, pos)
+        } else if self.board.highlight() == Some(self.board_pos) {
+            draw_rect!(self.config.dimen, self.config.hint_color, ctx, pos)
         } else {
             Ok(())
         }
     }
 
+    fn draw_cursor(&self, ctx: &DrawContext, pos: Point) -> MsResult {
+        let cursor = &self.config.cursor;
+        let Dimen { x: w, y: h } = self.config.dimen;
+        let width = cursor.width.min(w / 2).min(h / 2);
+        let (w_i, h_i, width_i) = (w as i32, h as i32, width as i32);
+
+        let mut canvas = ctx.canvas();
+        canvas.set_draw_color(cursor.color);
+        canvas.fill_rects(&[
+            Rect::new(pos.x, pos.y, w, width),
+            Rect::new(pos.x, pos.y + h_i - width_i, w, width),
+            Rect::new(pos.x, pos.y, width, h),
+            Rect::new(pos.x + w_i - width_i, pos.y, width, h),
+        ])
+    }
+
     fn draw_centered_rect(
         &self,
         ctx: &DrawContext,
@@ -72,17 +104,30 @@ impl DrawCell<'_> {
     }
 
     fn draw_hint(&self, ctx: &DrawContext, pos: Point, hint: usize) -> MsResult {
-        let text = text::make_text(ctx, Text::new(hint, "board.cell", self.config.text_color))?;
+        self.draw_centered_text(
+            ctx,
+            pos,
+            Text::new(hint, "board.cell", self.config.text_color),
+        )
+    }
+
+    fn draw_centered_text<T: ToString>(
+        &self,
+        ctx: &DrawContext,
+        pos: Point,
+        text: Text<T>,
+    ) -> MsResult {
+        let text = text::make_text(ctx, text)?;
         let tq = text.query();
 
         let cell_dimen = &self.config.dimen.as_i32();
         let tex_dimen = point!(tq.width as i32, tq.height as i32);
-        let hint_pos = pos + *cell_dimen / (2, 2) - tex_dimen / (2, 2);
+        let text_pos = pos + *cell_dimen / (2, 2) - tex_dimen / (2, 2);
 
         ctx.canvas().copy(
             text.texture(),
             None,
-            Some(Rect::new(hint_pos.x, hint_pos.y, tq.width, tq.height)),
+            Some(Rect::new(text_pos.x, text_pos.y, tq.width, tq.height)),
         )
     }
 }
@@ -0,0 +1,87 @@
+use crate::board::Board;
+use crate::solver::{self, Probabilities, View};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Manages the mine probability overlay drawn on top of the hidden cells
+#[derive(new, Default)]
+pub struct Heatmap {
+    #[new(default)]
+    is_visible: bool,
+    #[new(default)]
+    cache: Option<(View, Probabilities)>,
+    #[new(default)]
+    pending: Option<View>,
+    #[new(default)]
+    worker: Option<Worker>,
+}
+
+impl Heatmap {
+    /// Returns true if the overlay should be drawn
+    pub fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Shows the overlay if it is hidden and hides it otherwise
+    pub fn toggle(&mut self) {
+        self.is_visible = !self.is_visible
+    }
+
+    /// Returns `Some(&Probabilities)` of the specified `Board`, or `None` if
+    /// none have been calculated yet. Calculating them can take a while on
+    /// large boards, so whenever what the player can see has changed they are
+    /// recalculated on another thread, and the last ones calculated are
+    /// returned until then. These may include cells that have been revealed or
+    /// flagged since.
+    pub fn probabilities(&mut self, board: &Board) -> Option<&Probabilities> {
+        let worker = self.worker.get_or_insert_with(Worker::spawn);
+
+        for (view, probs) in worker.results.try_iter() {
+            if self.pending.as_ref() == Some(&view) {
+                self.pending = None;
+            }
+            self.cache = Some((view, probs));
+        }
+
+        let view = View::from(board);
+        let is_known = matches!(&self.cache, Some((v, _)) if *v == view)
+            || self.pending.as_ref() == Some(&view);
+
+        if !is_known && worker.views.send(view.clone()).is_ok() {
+            self.pending = Some(view);
+        }
+
+        self.cache.as_ref().map(|(_, probs)| probs)
+    }
+}
+
+/// Calculates the probabilities of the views sent to it one at a time on its
+/// own thread, which stops once the `Worker` is dropped
+struct Worker {
+    views: Sender<View>,
+    results: Receiver<(View, Probabilities)>,
+}
+
+impl Worker {
+    fn spawn() -> Self {
+        let (views, view_rx) = mpsc::channel::<View>();
+        let (result_tx, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            while let Ok(mut view) = view_rx.recv() {
+                // views that changed again while the last ones were calculated
+                // are out of date already
+                while let Ok(v) = view_rx.try_recv() {
+                    view = v;
+                }
+
+                let probs = solver::probabilities(&view);
+                if result_tx.send((view, probs)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { views, results }
+    }
+}
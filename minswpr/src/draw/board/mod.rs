@@ -1,27 +1,41 @@
 mod cell;
+/// Implements the mine probability overlay
+pub mod heatmap;
 
 use self::cell::DrawCellBuilder;
+use self::heatmap::Heatmap;
 use super::{Draw, DrawContext};
-use crate::board::Board;
+use crate::board::CellFlags;
 use crate::config::CellConfig;
+use crate::control::Cursor;
+use crate::game::Game;
 use crate::math::{Dimen, Point};
 use crate::{utils, ModelRef, MsResult};
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
+use sdl2::render::BlendMode;
 
 #[derive(AsAny)]
 pub struct DrawBoard {
-    board: ModelRef<Board>,
+    game: ModelRef<Game>,
+    heatmap: ModelRef<Heatmap>,
+    cursor: ModelRef<Cursor>,
     dimen: Dimen,
     cell_config: CellConfig,
 }
 
 impl DrawBoard {
-    pub fn new(board: ModelRef<Board>, cell_config: CellConfig) -> Self {
+    pub fn new(
+        game: ModelRef<Game>,
+        heatmap: ModelRef<Heatmap>,
+        cursor: ModelRef<Cursor>,
+        cell_config: CellConfig,
+    ) -> Self {
         let cell_dimen = cell_config.dimen.as_i32();
         let border_width = cell_config.border_width as i32;
 
-        let board_cell_dimen = utils::borrow_safe(&board.as_ref(), |b| {
-            point!(b.width() as i32, b.height() as i32)
+        let board_cell_dimen = utils::borrow_safe(&game.as_ref(), |g| {
+            point!(g.board().width() as i32, g.board().height() as i32)
         });
 
         let board_px_dimen = cell_dimen * board_cell_dimen
@@ -30,7 +44,9 @@ impl DrawBoard {
         let dimen = point!(board_px_dimen.x as u32, board_px_dimen.y as u32);
 
         Self {
-            board,
+            game,
+            heatmap,
+            cursor,
             cell_config,
             dimen,
         }
@@ -41,7 +57,13 @@ impl Draw for DrawBoard {
     fn draw(&mut self, ctx: &DrawContext, pos: Point) -> MsResult {
         draw_rect!(self.dimen, self.cell_config.color, ctx, pos)?;
         self.draw_cell_borders(ctx, pos)?;
-        self.draw_cells(ctx, pos)
+        self.draw_cells(ctx, pos)?;
+
+        if self.heatmap.borrow().is_visible() {
+            self.draw_heatmap(ctx, pos)
+        } else {
+            Ok(())
+        }
     }
 
     fn dimen(&self) -> Dimen {
@@ -86,7 +108,8 @@ impl DrawBoard {
     }
 
     fn draw_cells(&self, ctx: &DrawContext, pos: Point) -> MsResult {
-        let b = self.board.borrow();
+        let game = self.game.borrow();
+        let b = game.board();
         for x in 0..b.width() as u32 {
             for y in 0..b.height() as u32 {
                 self.draw_cell(ctx, pos, x, y)?;
@@ -98,13 +121,55 @@ impl DrawBoard {
     fn draw_cell(&self, ctx: &DrawContext, pos: Point, x: u32, y: u32) -> MsResult {
         let screen_pos = Self::calc_cell_screen_pos(point!(x, y), pos, &self.cell_config);
         DrawCellBuilder::default()
-            .board(&self.board.borrow())
+            .board(self.game.borrow().board())
             .board_pos(point!(x, y))
+            .is_cursor(self.cursor.borrow().pos() == Some(point!(x, y)))
             .config(&self.cell_config)
             .build()?
             .draw(ctx, screen_pos)
     }
 
+    fn draw_heatmap(&self, ctx: &DrawContext, pos: Point) -> MsResult {
+        let game = self.game.borrow();
+        let mut heatmap = self.heatmap.borrow_mut();
+        let config = &self.cell_config.heatmap;
+
+        let board = game.board();
+        let probs = match heatmap.probabilities(board) {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        let mut canvas = ctx.canvas();
+        let blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        // the probabilities may be from before the last move
+        let is_hidden = |p: &Point<u32>| {
+            !board
+                .cell(p.x, p.y)
+                .intersects(CellFlags::REVEALED | CellFlags::FLAG)
+        };
+
+        for (cell_pos, p) in probs.iter().filter(|(cell_pos, _)| is_hidden(cell_pos)) {
+            let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * p).round() as u8;
+            let (safe, mine) = (config.safe_color, config.mine_color);
+            canvas.set_draw_color(Color::RGBA(
+                lerp(safe.r, mine.r),
+                lerp(safe.g, mine.g),
+                lerp(safe.b, mine.b),
+                config.alpha,
+            ));
+
+            let screen_pos = Self::calc_cell_screen_pos(cell_pos, pos, &self.cell_config);
+            let Dimen { x: w, y: h } = self.cell_config.dimen;
+            canvas.fill_rect(Rect::new(screen_pos.x, screen_pos.y, w, h))?;
+        }
+
+        canvas.set_blend_mode(blend_mode);
+        Ok(())
+    }
+
     fn calc_cell_screen_pos(cell_pos: Point<u32>, board_pos: Point, config: &CellConfig) -> Point {
         let cell_pos = cell_pos.as_i32();
         let cell_dimen = &config.dimen.as_i32();
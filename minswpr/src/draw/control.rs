@@ -1,13 +1,12 @@
 use super::Draw;
-use crate::board::Board;
 use crate::config::{LedDisplayConfig, ResetButtonConfig};
-use crate::control::{Button, Stopwatch};
+use crate::control::Button;
 use crate::draw::text::TextResult;
 use crate::draw::text::{self, Text};
 use crate::draw::DrawContext;
 use crate::draw::Margins;
+use crate::game::{Game, GameState};
 use crate::math::{Dimen, Point};
-use crate::GameState;
 use crate::{utils, ModelRef, MsResult};
 use sdl2::rect::Rect;
 use std::cmp;
@@ -47,24 +46,28 @@ pub struct DrawLedDisplay {
 }
 
 pub enum LedDisplayKind {
-    FlagCounter(ModelRef<Board>),
-    Stopwatch(ModelRef<Stopwatch>),
+    FlagCounter(ModelRef<Game>),
+    Stopwatch(ModelRef<Game>),
 }
 
 impl Draw for DrawLedDisplay {
     fn draw(&mut self, ctx: &DrawContext, pos: Point) -> MsResult {
         let text = self.make_text(ctx)?;
         let tq = text.query();
-        let pos = pos + point!(self.dimen().width(), 0).as_i32() - point!(tq.width, 0).as_i32();
+
+        // text that is too wide for the font is cut off on the left, so that
+        // the ones digits stay visible
+        let width = cmp::min(tq.width, self.dimen().width());
+        let pos = pos + point!(self.dimen().width() - width, 0).as_i32();
         ctx.canvas().copy(
             text.texture(),
-            None,
-            Some(Rect::new(pos.x, pos.y, tq.width, tq.height)),
+            Some(Rect::new((tq.width - width) as i32, 0, width, tq.height)),
+            Some(Rect::new(pos.x, pos.y, width, tq.height)),
         )
     }
 
     fn dimen(&self) -> Dimen {
-        self.config.dimen
+        self.config.fitted_dimen()
     }
 }
 
@@ -75,22 +78,27 @@ impl DrawLedDisplay {
     fn make_text<'a>(&self, ctx: &'a DrawContext<'a>) -> TextResult<'a> {
         let normal_val = |i| cmp::max(Self::MIN_VALUE, cmp::min(Self::MAX_VALUE, i));
         let text_color = self.config.text_color;
-        text::make_text(ctx, match &self.kind {
-            LedDisplayKind::FlagCounter(board) => {
-                let flags_remaining = utils::borrow_safe(&board.as_ref(), |b| {
-                    b.num_mines() as i32 - b.count_flags() as i32
-                });
-                Text::new(
-                    normal_val(flags_remaining),
-                    "control.flag_counter",
+        text::make_text(
+            ctx,
+            match &self.kind {
+                LedDisplayKind::FlagCounter(game) => {
+                    let flags_remaining = utils::borrow_safe(&game.as_ref(), |g| {
+                        g.board().num_mines() as i32 - g.board().count_flags() as i32
+                    });
+                    Text::new(
+                        normal_val(flags_remaining).to_string(),
+                        "control.flag_counter",
+                        text_color,
+                    )
+                }
+                LedDisplayKind::Stopwatch(game) => Text::new(
+                    self.config
+                        .format
+                        .format(game.borrow().stopwatch().elapsed()),
+                    "control.stopwatch",
                     text_color,
-                )
-            }
-            LedDisplayKind::Stopwatch(stopwatch) => Text::new(
-                normal_val(stopwatch.borrow().elapsed().as_secs() as i32),
-                "control.stopwatch",
-                text_color,
-            ),
-        })
+                ),
+            },
+        )
     }
 }
@@ -6,6 +6,8 @@ pub mod board;
 /// Implements the components required to draw the control panel located above
 /// the board
 pub mod control;
+/// Draws messages over the whole window
+pub mod overlay;
 /// Implements text rendering
 pub mod text;
 
@@ -0,0 +1,51 @@
+use super::text::{self, Text};
+use super::DrawContext;
+use crate::config::OverlayConfig;
+use crate::math::Dimen;
+use crate::MsResult;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::BlendMode;
+
+/// Shades the whole window of the specified `dimen` and draws the specified
+/// `lines` of text centered over it
+pub fn draw_overlay(
+    ctx: &DrawContext,
+    dimen: Dimen,
+    lines: &[String],
+    config: &OverlayConfig,
+) -> MsResult {
+    {
+        let mut canvas = ctx.canvas();
+        let blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        let Color { r, g, b, .. } = config.color;
+        canvas.set_draw_color(Color::RGBA(r, g, b, config.alpha));
+        canvas.fill_rect(Rect::new(0, 0, dimen.width(), dimen.height()))?;
+
+        canvas.set_blend_mode(blend_mode);
+    }
+
+    let rendered = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| text::make_text(ctx, Text::new(l, "overlay", config.text_color)))
+        .collect::<MsResult<Vec<_>>>()?;
+
+    let total_height = rendered.iter().map(|t| t.query().height).sum::<u32>() as i32;
+    let mut y = (dimen.height() as i32 - total_height) / 2;
+
+    for text in &rendered {
+        let tq = text.query();
+        let x = (dimen.width() as i32 - tq.width as i32) / 2;
+        ctx.canvas().copy(
+            text.texture(),
+            None,
+            Some(Rect::new(x, y, tq.width, tq.height)),
+        )?;
+        y += tq.height as i32;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,446 @@
+#[cfg(test)]
+mod tests;
+
+use crate::board::{Action, Board, CellFlags};
+use crate::control::{Clock, Stopwatch};
+use crate::math::Point;
+use crate::record::{GameLog, GameRecord};
+use crate::save::SaveGame;
+use crate::solver::{self, Hint, View};
+
+/// Something that happened as the result of a call to one of the `Game`
+/// actions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A board that can be cleared without guessing was requested, but none
+    /// was found within `Board::max_no_guess_attempts` layouts, so the board
+    /// that was placed may need guessing
+    GuessRequired,
+    /// The first cell was revealed and the stopwatch was started
+    Started,
+    /// The specified cells were revealed
+    Revealed(Vec<Point<u32>>),
+    /// The specified cell was flagged
+    Flagged(Point<u32>),
+    /// The flag on the specified cell was replaced by a question mark
+    Questioned(Point<u32>),
+    /// The flag or question mark on the specified cell was removed
+    Unflagged(Point<u32>),
+    /// A mine was revealed at the specified cell
+    Exploded(Point<u32>),
+    /// The specified `Action` was undone
+    Undone(Action),
+    /// The specified `Action` was redone
+    Redone(Action),
+    /// An action that ended the game was undone, or a paused game was
+    /// continued
+    Resumed,
+    /// The game was paused and the stopwatch stopped
+    Paused,
+    /// Every safe cell was revealed
+    Won,
+    /// A mine was revealed
+    Lost,
+}
+
+/// The rules of a game of minswpr, independent of how the game is displayed.
+/// A `Game` owns the `Board`, the `GameState` and the `Stopwatch` of a single
+/// game and moves them forward as the player reveals and flags cells.
+#[derive(Clone)]
+pub struct Game {
+    board: Board,
+    state: GameState,
+    stopwatch: Stopwatch,
+    log: GameLog,
+    safe_area: bool,
+    no_guess: bool,
+}
+
+impl Game {
+    /// Creates a new `Game` on the specified `board`. If the mines of the board
+    /// have not been placed yet, they are placed when the first cell is
+    /// revealed; `safe_area` and `no_guess` are passed to `Board::generate`.
+    pub fn new(board: Board, safe_area: bool, no_guess: bool) -> Self {
+        Self {
+            board,
+            state: GameState::Ready,
+            stopwatch: Stopwatch::new(),
+            log: GameLog::new(),
+            safe_area,
+            no_guess,
+        }
+    }
+
+    /// Creates a new `Game` like `Game::new` whose stopwatch reads the time from
+    /// the specified `clock` instead of the system clock
+    pub fn with_clock(board: Board, safe_area: bool, no_guess: bool, clock: Clock) -> Self {
+        Self {
+            stopwatch: Stopwatch::with_clock(clock),
+            ..Self::new(board, safe_area, no_guess)
+        }
+    }
+
+    /// Creates a new `Game` that continues the specified `save`. If the saved
+    /// game had already started, the stopwatch keeps counting from the saved
+    /// time, unless it was saved while paused and stays paused until
+    /// `Game::resume` is called.
+    pub fn from_save(save: SaveGame, safe_area: bool, no_guess: bool) -> Self {
+        let SaveGame {
+            board,
+            mut stopwatch,
+            game_log,
+            is_paused,
+            ..
+        } = save;
+
+        let state = if !board.is_generated() {
+            GameState::Ready
+        } else if is_paused {
+            GameState::Paused
+        } else {
+            stopwatch.resume();
+            GameState::Started
+        };
+
+        Self {
+            board,
+            state,
+            stopwatch,
+            log: game_log,
+            safe_area,
+            no_guess,
+        }
+    }
+
+    /// Returns the `Board` being played on
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the `Board` being played on for changes that are not part of
+    /// the rules, like marking cells as pressed
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    /// Returns true if the neighbors of the first revealed cell are kept clear
+    /// of mines
+    pub fn safe_area(&self) -> bool {
+        self.safe_area
+    }
+
+    /// Returns true if the board is generated so that it can be cleared
+    /// without guessing
+    pub fn no_guess(&self) -> bool {
+        self.no_guess
+    }
+
+    /// Returns the current `GameState`, which is one of `Ready`, `Started`,
+    /// `Paused` or `Over`
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Returns true if the game has been won or lost
+    pub fn is_over(&self) -> bool {
+        matches!(self.state, GameState::Over(_))
+    }
+
+    /// Returns true if the game is paused
+    pub fn is_paused(&self) -> bool {
+        self.state == GameState::Paused
+    }
+
+    /// Returns the `Stopwatch` timing the game
+    pub fn stopwatch(&self) -> &Stopwatch {
+        &self.stopwatch
+    }
+
+    /// Returns the `GameLog` of the help used and clicks made during the game
+    pub fn log(&self) -> &GameLog {
+        &self.log
+    }
+
+    /// Returns the `GameLog` of the game, for counting the clicks made
+    pub fn log_mut(&mut self) -> &mut GameLog {
+        &mut self.log
+    }
+
+    /// Returns `Some(GameRecord)` with the result of the game if it is over,
+    /// `None` otherwise
+    pub fn record(&self) -> Option<GameRecord> {
+        match self.state {
+            GameState::Over(did_win) => Some(GameRecord::new(
+                &self.board,
+                did_win,
+                self.stopwatch.elapsed(),
+                &self.log,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some(SaveGame)` of the game if it is in progress or paused.
+    /// Returns `None` if no cell has been revealed yet or the game is over.
+    pub fn save(&self) -> Option<SaveGame> {
+        match self.state {
+            GameState::Started | GameState::Paused => Some(SaveGame::new(
+                self.board.clone(),
+                self.stopwatch.clone(),
+                self.log,
+                self.is_paused(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Reveals the cell at the specified position. The first reveal places the
+    /// mines if needed and starts the game, see `Event::GuessRequired`. This
+    /// method has no effect if the game is paused or over, or the cell is
+    /// flagged or already revealed.
+    pub fn reveal(&mut self, x: u32, y: u32) -> Vec<Event> {
+        if self.is_over() || self.is_paused() {
+            return vec![];
+        }
+
+        // nothing would be revealed, so the mines are not placed around it
+        if self
+            .board
+            .cell(x, y)
+            .intersects(CellFlags::FLAG | CellFlags::REVEALED)
+        {
+            return vec![];
+        }
+
+        let mut events = vec![];
+
+        if !self.board.is_generated() && !self.board.generate(x, y, self.safe_area, self.no_guess) {
+            events.push(Event::GuessRequired);
+        }
+
+        if let GameState::Ready = self.state {
+            self.stopwatch.start();
+            self.state = GameState::Started;
+            events.push(Event::Started);
+        }
+
+        let revealed = self.apply(Action::Reveal(point!(x, y)));
+        if revealed.is_empty() {
+            return events;
+        }
+
+        if self.board.cell(x, y).contains(CellFlags::MINE) {
+            // hit a mine :(
+            events.push(Event::Exploded(point!(x, y)));
+            events.push(self.end(false));
+        } else {
+            events.push(Event::Revealed(revealed));
+            events.extend(self.check_did_win());
+        }
+
+        events
+    }
+
+    /// Reveals the neighbors of the revealed number at the specified position
+    /// if it has as many flags around it as adjacent mines. This method has no
+    /// effect unless the game has started.
+    pub fn chord(&mut self, x: u32, y: u32) -> Vec<Event> {
+        if self.state != GameState::Started {
+            return vec![];
+        }
+
+        let revealed = self.apply(Action::RevealArea(point!(x, y)));
+        if revealed.is_empty() {
+            return vec![];
+        }
+
+        let mines = revealed
+            .iter()
+            .filter(|p| self.board.cell(p.x, p.y).contains(CellFlags::MINE))
+            .map(|p| Event::Exploded(*p))
+            .collect::<Vec<_>>();
+
+        if mines.is_empty() {
+            let mut events = vec![Event::Revealed(revealed)];
+            events.extend(self.check_did_win());
+            events
+        } else {
+            let mut events = mines;
+            events.push(self.end(false));
+            events
+        }
+    }
+
+    /// Flags the hidden cell at the specified position, or removes its flag if
+    /// it is flagged, like `Board::toggle_flag`. This method has no effect if
+    /// the game is paused or over.
+    pub fn toggle_flag(&mut self, x: u32, y: u32) -> Vec<Event> {
+        if self.is_over() || self.is_paused() {
+            return vec![];
+        }
+
+        let p = point!(x, y);
+        if self.apply(Action::ToggleFlag(p)).is_empty() {
+            return vec![];
+        }
+
+        let cell = self.board.cell(x, y);
+        if cell.contains(CellFlags::FLAG) {
+            vec![Event::Flagged(p)]
+        } else if cell.contains(CellFlags::QUESTION) {
+            vec![Event::Questioned(p)]
+        } else {
+            vec![Event::Unflagged(p)]
+        }
+    }
+
+    /// Highlights the cell suggested by `solver::hint` and counts the hint
+    /// towards the game if the mines have been placed and the cell was not
+    /// highlighted already. Returns `None` if the game is paused or over, or
+    /// there is nothing left to suggest.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if self.is_over() || self.is_paused() {
+            return None;
+        }
+
+        let hint = solver::hint(&View::from(&self.board))?;
+        // pressing it again on the same cell or before the mines are placed
+        // gives nothing away
+        if self.board.is_generated() && self.board.highlight() != Some(hint.pos()) {
+            self.log.add_hint();
+        }
+
+        self.board.set_highlight(Some(hint.pos()));
+        Some(hint)
+    }
+
+    /// Marks the game as one where the mine probabilities were shown, if the
+    /// mines have been placed and the game is not over. Returns true if it was
+    /// not marked already.
+    pub fn show_heatmap(&mut self) -> bool {
+        if self.is_over() || !self.board.is_generated() || self.log.heatmap_used() {
+            return false;
+        }
+
+        self.log.set_heatmap_used();
+        true
+    }
+
+    /// Undoes the last action on the board and marks the game as one where
+    /// undo was used. If the action ended the game, the game continues. This
+    /// method has no effect if the game is paused.
+    pub fn undo(&mut self) -> Vec<Event> {
+        if self.is_paused() {
+            return vec![];
+        }
+
+        let action = match self.board.undo() {
+            Some(a) => a,
+            None => return vec![],
+        };
+
+        self.board.set_highlight(None);
+        self.log.set_undo_used();
+
+        let mut events = vec![Event::Undone(action)];
+        if self.is_over() {
+            self.stopwatch.resume();
+            self.state = GameState::Started;
+            events.push(Event::Resumed);
+        }
+
+        events
+    }
+
+    /// Redoes the last undone action on the board, ending the game if it
+    /// reveals a mine or the last safe cell. This method has no effect if the
+    /// game is paused or over.
+    pub fn redo(&mut self) -> Vec<Event> {
+        if self.is_over() || self.is_paused() {
+            return vec![];
+        }
+
+        let action = match self.board.redo() {
+            Some(a) => a,
+            None => return vec![],
+        };
+
+        self.board.set_highlight(None);
+
+        let mut events = vec![Event::Redone(action)];
+        if self.board.has_revealed_mine() {
+            events.push(self.end(false));
+        } else {
+            events.extend(self.check_did_win());
+        }
+
+        events
+    }
+
+    /// Stops the stopwatch until the game is resumed with `Game::resume`. This
+    /// method has no effect unless the game has started and is not over.
+    pub fn pause(&mut self) -> Vec<Event> {
+        if self.state != GameState::Started {
+            return vec![];
+        }
+
+        self.stopwatch.stop();
+        self.state = GameState::Paused;
+        vec![Event::Paused]
+    }
+
+    /// Continues a game paused with `Game::pause`, counting on from the time it
+    /// was paused at. This method has no effect if the game is not paused.
+    pub fn resume(&mut self) -> Vec<Event> {
+        if !self.is_paused() {
+            return vec![];
+        }
+
+        self.stopwatch.resume();
+        self.state = GameState::Started;
+        vec![Event::Resumed]
+    }
+
+    fn apply(&mut self, action: Action) -> Vec<Point<u32>> {
+        self.board.set_highlight(None);
+        self.board.apply(action)
+    }
+
+    fn check_did_win(&mut self) -> Option<Event> {
+        if self.board.remaining_cells().len() != self.board.num_mines() {
+            return None;
+        }
+
+        self.board.flag_remaining();
+
+        Some(self.end(true))
+    }
+
+    fn end(&mut self, did_win: bool) -> Event {
+        self.stopwatch.stop();
+        self.state = GameState::Over(did_win);
+        if did_win {
+            Event::Won
+        } else {
+            Event::Lost
+        }
+    }
+}
+
+/// Represents different states the application can be in
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameState {
+    Unknown,
+    Ready,
+    Started,
+    Paused,
+    Over(bool),
+    Reset,
+    Quit,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
@@ -0,0 +1,331 @@
+use super::{Event, Game, GameState};
+use crate::board::{Action, Board, CellFlags};
+use crate::math::Point;
+use crate::solver::Hint;
+
+fn make_game(board: &str) -> Game {
+    Game::new(board.parse().unwrap(), false, false)
+}
+
+#[test]
+fn test_win() {
+    let mut game = make_game(
+        "*..
+         ...
+         ...",
+    );
+    assert_eq!(GameState::Ready, game.state());
+    assert!(game.record().is_none());
+
+    let events = game.reveal(2, 2);
+    assert_eq!(3, events.len());
+    assert_eq!(Event::Started, events[0]);
+    match &events[1] {
+        Event::Revealed(cells) => assert_eq!(8, cells.len()),
+        e => panic!("unexpected event {:?}", e),
+    }
+    assert_eq!(Event::Won, events[2]);
+
+    assert_eq!(GameState::Over(true), game.state());
+    assert!(game.board().cell(0, 0).contains(CellFlags::FLAG));
+
+    let record = game.record().unwrap();
+    assert!(record.did_win);
+    assert_eq!(1, record.num_mines);
+    assert_eq!(1, record.bbbv);
+
+    // the board is frozen once the game is over
+    assert!(game.reveal(0, 0).is_empty());
+    assert!(game.toggle_flag(0, 0).is_empty());
+    assert!(game.hint().is_none());
+}
+
+#[test]
+fn test_win_undo_redo() {
+    let mut game = make_game(
+        "*..
+         ...
+         ...",
+    );
+    game.reveal(1, 0);
+    game.reveal(2, 2);
+    assert_eq!(GameState::Over(true), game.state());
+    assert!(game.board().cell(0, 0).contains(CellFlags::FLAG));
+
+    // the mines flagged by the win are undone with the winning action
+    assert_eq!(
+        vec![Event::Undone(Action::Reveal(point!(2, 2))), Event::Resumed],
+        game.undo()
+    );
+    assert_eq!(GameState::Started, game.state());
+    assert_eq!(0, game.board().count_flags());
+
+    assert_eq!(
+        vec![Event::Redone(Action::Reveal(point!(2, 2))), Event::Won],
+        game.redo()
+    );
+    assert!(game.board().cell(0, 0).contains(CellFlags::FLAG));
+}
+
+#[test]
+fn test_lose_undo_redo() {
+    let mut game = make_game(
+        "*..
+         ...
+         ...",
+    );
+
+    assert_eq!(
+        vec![Event::Started, Event::Exploded(point!(0, 0)), Event::Lost],
+        game.reveal(0, 0)
+    );
+    assert_eq!(GameState::Over(false), game.state());
+    assert!(!game.record().unwrap().did_win);
+    assert!(game.reveal(2, 2).is_empty());
+
+    assert_eq!(
+        vec![Event::Undone(Action::Reveal(point!(0, 0))), Event::Resumed],
+        game.undo()
+    );
+    assert_eq!(GameState::Started, game.state());
+    assert!(game.log().undo_used());
+    assert!(game.record().is_none());
+
+    assert_eq!(
+        vec![Event::Redone(Action::Reveal(point!(0, 0))), Event::Lost],
+        game.redo()
+    );
+    assert_eq!(GameState::Over(false), game.state());
+    assert!(game.record().unwrap().undo_used);
+}
+
+#[test]
+fn test_chord() {
+    let mut game = make_game(
+        "*.*
+         ...
+         ...",
+    );
+
+    // nothing to chord before the game starts
+    assert!(game.chord(1, 1).is_empty());
+
+    game.reveal(1, 2);
+    assert_eq!(GameState::Started, game.state());
+    assert_eq!(vec![Event::Flagged(point!(0, 0))], game.toggle_flag(0, 0));
+
+    // not enough flags around the number
+    assert!(game.chord(1, 1).is_empty());
+
+    game.toggle_flag(2, 0);
+    assert_eq!(
+        vec![Event::Revealed(vec![point!(1, 0)]), Event::Won],
+        game.chord(1, 1)
+    );
+}
+
+#[test]
+fn test_chord_wrong_flag() {
+    let mut game = make_game(
+        "*..
+         ...
+         ..*",
+    );
+
+    game.reveal(0, 2);
+    game.toggle_flag(1, 0);
+    game.toggle_flag(2, 2);
+
+    let events = game.chord(1, 1);
+    assert_eq!(Some(&Event::Exploded(point!(0, 0))), events.first());
+    assert_eq!(Some(&Event::Lost), events.last());
+    assert_eq!(GameState::Over(false), game.state());
+}
+
+#[test]
+fn test_toggle_flag() {
+    let mut game = make_game(
+        "*..
+         ...",
+    );
+
+    assert_eq!(vec![Event::Flagged(point!(0, 0))], game.toggle_flag(0, 0));
+    assert_eq!(vec![Event::Unflagged(point!(0, 0))], game.toggle_flag(0, 0));
+
+    // flagging does not start the game
+    assert_eq!(GameState::Ready, game.state());
+
+    game.reveal(2, 1);
+    assert!(game.toggle_flag(2, 1).is_empty());
+}
+
+#[test]
+fn test_reveal_flagged_first() {
+    let board = Board::new_deferred(3, 3, 1, 0).unwrap();
+    let mut game = Game::new(board, false, false);
+    game.toggle_flag(1, 1);
+
+    // the flagged cell is not revealed, so the game does not start
+    assert!(game.reveal(1, 1).is_empty());
+    assert_eq!(GameState::Ready, game.state());
+    assert!(!game.board().is_generated());
+    assert!(!game.stopwatch().is_running());
+
+    game.toggle_flag(1, 1);
+    assert_eq!(Event::Started, game.reveal(1, 1)[0]);
+    assert!(game.board().is_generated());
+}
+
+#[test]
+fn test_pause() {
+    let mut game = make_game(
+        "*..
+         ...
+         ..*",
+    );
+
+    // nothing to pause before the game starts
+    assert!(game.pause().is_empty());
+
+    game.reveal(0, 2);
+    assert_eq!(vec![Event::Paused], game.pause());
+    assert_eq!(GameState::Paused, game.state());
+    assert!(!game.stopwatch().is_running());
+
+    // the board does not take input while paused
+    assert!(game.reveal(2, 0).is_empty());
+    assert!(game.toggle_flag(0, 0).is_empty());
+    assert!(game.hint().is_none());
+    assert!(game.undo().is_empty());
+    assert!(game.save().is_some());
+
+    assert_eq!(vec![Event::Resumed], game.resume());
+    assert_eq!(GameState::Started, game.state());
+    assert!(game.stopwatch().is_running());
+    assert!(game.resume().is_empty());
+}
+
+#[test]
+fn test_save() {
+    let mut game = make_game(
+        "*..
+         ...
+         ..*",
+    );
+    assert!(game.save().is_none());
+
+    game.reveal(0, 2);
+    game.hint();
+    let save = game.save().unwrap();
+    assert_eq!(1, save.game_log.hints_used());
+
+    let resumed = Game::from_save(save, false, false);
+    assert_eq!(GameState::Started, resumed.state());
+    assert!(resumed.stopwatch().is_running());
+    assert_eq!(game.board().to_string(), resumed.board().to_string());
+
+    // a game saved while paused stays paused
+    game.pause();
+    let mut resumed = Game::from_save(game.save().unwrap(), false, false);
+    assert_eq!(GameState::Paused, resumed.state());
+    assert!(!resumed.stopwatch().is_running());
+    assert_eq!(vec![Event::Resumed], resumed.resume());
+    game.resume();
+
+    game.reveal(0, 0);
+    assert!(game.save().is_none());
+}
+
+#[test]
+fn test_hint_count() {
+    let board = Board::new_deferred(3, 3, 1, 0).unwrap();
+    let mut game = Game::new(board, false, false);
+    assert!(game.hint().is_some());
+    assert_eq!(0, game.log().hints_used());
+
+    let mut game = make_game(
+        "*..
+         ...
+         ..*",
+    );
+    game.reveal(0, 2);
+    game.hint();
+    game.hint();
+    assert_eq!(1, game.log().hints_used());
+
+    // undo removes the highlight, so the same hint counts again
+    let Point { x, y } = game.hint().unwrap().pos();
+    game.reveal(x, y);
+    game.undo();
+    game.hint();
+    assert_eq!(2, game.log().hints_used());
+}
+
+#[test]
+fn test_show_heatmap() {
+    // nothing is known before the mines are placed
+    let board = Board::new_deferred(3, 3, 1, 0).unwrap();
+    let mut game = Game::new(board, false, false);
+    assert!(!game.show_heatmap());
+    assert!(!game.log().heatmap_used());
+
+    let mut game = make_game(
+        "*..
+         ...
+         ..*",
+    );
+    assert!(game.show_heatmap());
+    assert!(!game.show_heatmap());
+    game.reveal(0, 2);
+    game.reveal(2, 0);
+    assert!(game.record().unwrap().heatmap_used);
+
+    let mut game = make_game("*.");
+    game.reveal(1, 0);
+    assert!(!game.show_heatmap());
+    assert!(!game.record().unwrap().heatmap_used);
+}
+
+#[test]
+fn test_full_game() {
+    let board = Board::new_deferred(16, 16, 40, 1234).unwrap();
+    let mut game = Game::new(board, true, true);
+
+    let events = game.reveal(8, 8);
+    assert_eq!(Some(&Event::Started), events.first());
+    assert!(game.board().is_generated());
+
+    // a no guess board can be won by only following the hints
+    while !game.is_over() {
+        let Point { x, y } = match game.hint() {
+            Some(Hint::Safe(p, _)) => p,
+            Some(Hint::Guess(p, m)) if m <= 0.0 => p,
+            h => panic!("expected a safe hint, got {:?}", h),
+        };
+        assert!(!game.reveal(x, y).contains(&Event::Lost));
+    }
+
+    let record = game.record().unwrap();
+    assert!(record.did_win);
+    assert_eq!(1234, record.seed);
+    assert!(record.hints_used > 0);
+}
+
+#[test]
+fn test_guess_required() {
+    // every layout leaves a coin flip between the two end cells
+    let board = Board::new_deferred(3, 1, 1, 0).unwrap();
+    let mut game = Game::new(board, false, true);
+    assert_eq!(
+        vec![
+            Event::GuessRequired,
+            Event::Started,
+            Event::Revealed(vec![point!(1, 0)])
+        ],
+        game.reveal(1, 0)
+    );
+
+    let board = Board::new_deferred(3, 1, 1, 0).unwrap();
+    let mut game = Game::new(board, false, false);
+    assert_eq!(Event::Started, game.reveal(1, 0)[0]);
+}
@@ -1,18 +1,20 @@
 use crate::board::CellFlags;
+use crate::control::Release;
+use crate::game::{Event, Game};
 use crate::math::Point;
+use crate::replay::Input;
 use crate::{utils, Context, GameState};
-use sdl2::mouse::MouseButton;
 
 use super::events::*;
 
 /// Event handler for receiving `DrawBoard` clicks
 ///
 /// This function has no effect if the current `GameState` is `Over`. Otherwise,
-/// it retrieves the cell at the clicked position and forwards it to another
-/// handler depending on which mouse button was released
-///
-/// If this is the first cell that was clicked on the board, the `GameState` is
-/// moved from `Ready` to `Start`
+/// it retrieves the cell at the clicked position and forwards it to the `Game`
+/// depending on which mouse button was released, as configured in
+/// `MouseConfig`: the reveal button reveals the cell, the flag button flags it
+/// and the chord button chords it. If the reveal and flag buttons were held
+/// together, releasing either of them chords the cell instead
 ///
 /// # Arguments
 /// * `ctx` - The game `Context`
@@ -20,100 +22,212 @@ use super::events::*;
 pub fn on_click_board(ctx: &Context, e: MouseUpEvent) -> GameState {
     let Point { x, y } = e.mouse_pos();
     let game_state = ctx.game_state();
+    let buttons = &ctx.config().mouse;
+    let btn = e.mouse_btn();
+
+    let release = if btn == buttons.reveal || btn == buttons.flag {
+        ctx.mouse_chord()
+            .borrow_mut()
+            .release(btn == buttons.reveal)
+    } else {
+        Release::Click
+    };
 
     // if the current game is over, freeze the board
     if let GameState::Over(_) = game_state {
         return game_state;
     }
 
-    match ctx.get_cell_at(x, y) {
-        Some(p) => {
-            self::check_did_win(ctx, {
-                let cell_pressed = ctx
-                    .board()
-                    .borrow()
-                    .cell(p.x, p.y)
-                    .contains(CellFlags::PRESSED);
-
-                // if the cell was not previously pressed, ignore this event
-                if !cell_pressed {
-                    return game_state;
-                }
-
-                // start the game when the first cell of a fresh board is clicked
-                let game_state = if let GameState::Ready = game_state {
-                    GameState::Start
-                } else {
-                    game_state
-                };
-
-                match &e.mouse_btn() {
-                    MouseButton::Left => self::on_left_click_cell(ctx, p, game_state),
-                    MouseButton::Middle => self::on_middle_click_cell(ctx, p, game_state),
-                    _ => game_state,
-                }
-            })
-        }
-        None => game_state,
+    let p = match ctx.get_cell_at(x, y) {
+        Some(p) => p,
+        None => return game_state,
+    };
+
+    let mut game = ctx.game().borrow_mut();
+    let is_pressed = game.board().cell(p.x, p.y).contains(CellFlags::PRESSED);
+
+    // a visible keyboard cursor follows the clicked cell
+    let mut cursor = ctx.cursor().borrow_mut();
+    if cursor.pos().is_some() {
+        cursor.set_pos(Some(p));
     }
-}
 
-fn check_did_win(ctx: &Context, game_state: GameState) -> GameState {
-    if let GameState::Over(_) = ctx.game_state() {
-        return game_state;
+    match release {
+        Release::Ignore => {}
+        Release::Chord if is_pressed => {
+            self::play(ctx, &mut game, Input::Chord(p.x, p.y));
+            game.board_mut().clear_all(CellFlags::PRESSED);
+        }
+        Release::Chord => {}
+        Release::Click if btn == buttons.flag => {
+            self::play(ctx, &mut game, Input::Flag(p.x, p.y));
+        }
+        // the other buttons only act on the cell they were pressed on
+        Release::Click if !is_pressed => {}
+        Release::Click if btn == buttons.reveal => {
+            self::play(ctx, &mut game, Input::Reveal(p.x, p.y));
+        }
+        Release::Click if btn == buttons.chord => {
+            self::play(ctx, &mut game, Input::Chord(p.x, p.y));
+        }
+        Release::Click => {}
     }
 
-    let (did_win, remaining) = utils::borrow_safe(ctx.board().as_ref(), |b| {
-        let r = b.remaining_cells();
-        (r.len() == b.num_mines(), r)
+    game.state()
+}
+
+/// Moves the keyboard cursor by the specified amount of cells, or shows it if
+/// it is hidden
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+/// * `dx` - The amount of cells to move right
+/// * `dy` - The amount of cells to move down
+pub fn on_move_cursor(ctx: &Context, dx: i32, dy: i32) -> GameState {
+    let dimen = utils::borrow_safe(ctx.game().as_ref(), |g| {
+        point!(g.board().width(), g.board().height())
     });
+    let mut cursor = ctx.cursor().borrow_mut();
+    let last_pos = cursor.pos();
+    cursor.move_by(dx, dy, dimen);
 
-    if did_win {
-        for Point { x, y } in remaining {
-            ctx.board()
-                .borrow_mut()
-                .cell_mut(x, y)
-                .insert(CellFlags::FLAG);
-        }
-        GameState::Over(true)
-    } else {
-        game_state
+    if let Some(Point { x, y }) = cursor.pos().filter(|p| Some(*p) != last_pos) {
+        ctx.recorder()
+            .borrow_mut()
+            .record(&ctx.game().borrow(), Input::Cursor(x, y));
     }
+
+    ctx.game_state()
 }
 
-fn on_left_click_cell(
-    ctx: &Context,
-    Point { x, y }: Point<u32>,
-    game_state: GameState,
-) -> GameState {
-    let mut board = ctx.board().borrow_mut();
-    let num_revealed = board.reveal_from(x, y);
-    if num_revealed > 0 && board.cell(x, y).contains(CellFlags::MINE) {
-        // hit a mine :(
-        GameState::Over(false)
-    } else {
-        game_state
+/// Reveals the cell under the keyboard cursor
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_reveal_cursor(ctx: &Context) -> GameState {
+    self::on_cursor(ctx, Input::Reveal)
+}
+
+/// Flags or unflags the cell under the keyboard cursor
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_flag_cursor(ctx: &Context) -> GameState {
+    self::on_cursor(ctx, Input::Flag)
+}
+
+/// Reveals the neighbors of the cell under the keyboard cursor if it is a
+/// satisfied number
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_chord_cursor(ctx: &Context) -> GameState {
+    self::on_cursor(ctx, Input::Chord)
+}
+
+fn on_cursor(ctx: &Context, input: fn(u32, u32) -> Input) -> GameState {
+    let Point { x, y } = match ctx.cursor().borrow().pos() {
+        Some(p) => p,
+        None => return ctx.game_state(),
+    };
+
+    let mut game = ctx.game().borrow_mut();
+    self::play(ctx, &mut game, input(x, y));
+    game.state()
+}
+
+/// Records the specified `input` to the replay of the current game and applies
+/// it to the `Game`
+fn play(ctx: &Context, game: &mut Game, input: Input) {
+    // the heatmap may have been shown since the game started or resumed
+    self::show_heatmap(ctx, game);
+    ctx.recorder().borrow_mut().record(game, input);
+
+    if input.apply(game).contains(&Event::GuessRequired) {
+        ctx.set_guess_required(true);
     }
+
+    self::show_heatmap(ctx, game);
 }
 
-fn on_middle_click_cell(
-    ctx: &Context,
-    Point { x, y }: Point<u32>,
-    game_state: GameState,
-) -> GameState {
-    let mut board = ctx.board().borrow_mut();
-    let mines_revealed = board
-        .reveal_area(x, y)
-        .iter()
-        .filter(|p| board.cell(p.x, p.y).contains(CellFlags::MINE))
-        .count();
-    if mines_revealed > 0 {
-        GameState::Over(false)
-    } else {
-        game_state
+/// Marks the current game as one where the mine probabilities were shown if
+/// the `Heatmap` is visible, and records it to the replay
+fn show_heatmap(ctx: &Context, game: &mut Game) {
+    if ctx.heatmap().borrow().is_visible() && game.show_heatmap() {
+        ctx.recorder().borrow_mut().record(game, Input::Heatmap);
     }
 }
 
+/// Shows the mine probability `Heatmap` if it is hidden and hides it
+/// otherwise. Showing it while the game is in progress marks the game as one
+/// where it was used.
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_heatmap(ctx: &Context) -> GameState {
+    ctx.heatmap().borrow_mut().toggle();
+    let mut game = ctx.game().borrow_mut();
+    self::show_heatmap(ctx, &mut game);
+    game.state()
+}
+
+/// Highlights the cell suggested by `solver::hint` and counts the hint towards
+/// the current game
+///
+/// This function has no effect if the current `GameState` is `Over`
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_hint(ctx: &Context) -> GameState {
+    let mut game = ctx.game().borrow_mut();
+    self::play(ctx, &mut game, Input::Hint);
+    game.state()
+}
+
+/// Pauses the current game, stopping the stopwatch and hiding the board
+///
+/// This function has no effect unless the current `GameState` is `Started`
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_pause(ctx: &Context) -> GameState {
+    let mut game = ctx.game().borrow_mut();
+    game.pause();
+    game.state()
+}
+
+/// Resumes the current game if it is paused
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_resume(ctx: &Context) -> GameState {
+    let mut game = ctx.game().borrow_mut();
+    game.resume();
+    game.state()
+}
+
+/// Undoes the last action on the board and marks the game as one where undo
+/// was used. If the game was over, it is resumed
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_undo(ctx: &Context) -> GameState {
+    let mut game = ctx.game().borrow_mut();
+    self::play(ctx, &mut game, Input::Undo);
+    game.state()
+}
+
+/// Redoes the last undone action on the board, ending the game if it reveals a
+/// mine or the last safe cell
+///
+/// # Arguments
+/// * `ctx` - The game `Context`
+pub fn on_redo(ctx: &Context) -> GameState {
+    let mut game = ctx.game().borrow_mut();
+    self::play(ctx, &mut game, Input::Redo);
+    game.state()
+}
+
 pub fn on_mouse_move_board(ctx: &Context, e: MouseMoveEvent) -> GameState {
     if let GameState::Over(_) = ctx.game_state() {
         return ctx.game_state();
@@ -122,7 +236,10 @@ pub fn on_mouse_move_board(ctx: &Context, e: MouseMoveEvent) -> GameState {
     let Point { x, y } = e.mouse_pos();
     match ctx.get_cell_at(x, y) {
         Some(p) => {
-            ctx.board().borrow_mut().clear_all(CellFlags::PRESSED);
+            ctx.game()
+                .borrow_mut()
+                .board_mut()
+                .clear_all(CellFlags::PRESSED);
 
             // handles case where cursor moves off board, cursor is released, cursor is
             // re-pressed off the board, and enters the board area once again
@@ -131,13 +248,22 @@ pub fn on_mouse_move_board(ctx: &Context, e: MouseMoveEvent) -> GameState {
             }
 
             let mouse = e.mouse_state();
-            if mouse.is_mouse_button_pressed(MouseButton::Middle) {
+            let buttons = &ctx.config().mouse;
+            let mut chord = ctx.mouse_chord().borrow_mut();
+            chord.sync(
+                mouse.is_mouse_button_pressed(buttons.reveal),
+                mouse.is_mouse_button_pressed(buttons.flag),
+            );
+
+            if mouse.is_mouse_button_pressed(buttons.chord) || chord.is_both_held() {
                 self::set_board_area_pressed(ctx, p);
-            } else if mouse.is_mouse_button_pressed(MouseButton::Left) {
-                ctx.board()
+            } else if chord.is_chording() {
+                // the rest of a chord that has been performed presses nothing
+            } else if mouse.is_mouse_button_pressed(buttons.reveal) {
+                ctx.game()
                     .borrow_mut()
-                    .cell_mut(p.x, p.y)
-                    .insert(CellFlags::PRESSED);
+                    .board_mut()
+                    .insert_flags(p.x, p.y, CellFlags::PRESSED);
             }
 
             ctx.game_state()
@@ -153,24 +279,31 @@ pub fn on_mouse_down_board(ctx: &Context, e: MouseDownEvent) -> GameState {
     }
 
     let Point { x, y } = e.mouse_pos();
-    let board = || ctx.board().borrow_mut();
+    let game = || ctx.game().borrow_mut();
     let set_not_released = || ctx.button("board").borrow_mut().set_released(false);
 
+    let buttons = &ctx.config().mouse;
+
     match (ctx.get_cell_at(x, y), e.mouse_btn()) {
-        (Some(p), MouseButton::Left) => {
-            board().cell_mut(p.x, p.y).insert(CellFlags::PRESSED);
-            set_not_released();
+        (Some(p), b) if b == buttons.reveal || b == buttons.flag => {
+            let is_reveal = b == buttons.reveal;
+            if ctx.mouse_chord().borrow_mut().press(is_reveal) {
+                // pressing the reveal and flag buttons together chords
+                game().board_mut().clear_all(CellFlags::PRESSED);
+                self::set_board_area_pressed(ctx, p);
+            } else if is_reveal {
+                game()
+                    .board_mut()
+                    .insert_flags(p.x, p.y, CellFlags::PRESSED);
+                set_not_released();
+            }
             ctx.game_state()
         }
-        (Some(p), MouseButton::Middle) => {
+        (Some(p), b) if b == buttons.chord => {
             self::set_board_area_pressed(ctx, p);
             set_not_released();
             ctx.game_state()
         }
-        (Some(p), MouseButton::Right) => {
-            board().toggle_flag(p.x, p.y);
-            ctx.game_state()
-        }
         _ => ctx.game_state(),
     }
 }
@@ -178,16 +311,18 @@ pub fn on_mouse_down_board(ctx: &Context, e: MouseDownEvent) -> GameState {
 fn set_board_area_pressed(ctx: &Context, Point { x, y }: Point<u32>) {
     ctx.button("board").borrow_mut().set_released(false);
 
-    let mut board = ctx.board().borrow_mut();
-    board.cell_mut(x, y).insert(CellFlags::PRESSED);
+    let mut game = ctx.game().borrow_mut();
+    let board = game.board_mut();
+    board.insert_flags(x, y, CellFlags::PRESSED);
     for neighbor in board.neighbors(x, y) {
-        board
-            .cell_mut(neighbor.x, neighbor.y)
-            .insert(CellFlags::PRESSED);
+        board.insert_flags(neighbor.x, neighbor.y, CellFlags::PRESSED);
     }
 }
 
 pub fn on_mouse_leave_board(ctx: &Context, _: MouseLeaveEvent) -> GameState {
-    ctx.board().borrow_mut().clear_all(CellFlags::PRESSED);
+    ctx.game()
+        .borrow_mut()
+        .board_mut()
+        .clear_all(CellFlags::PRESSED);
     ctx.game_state()
 }
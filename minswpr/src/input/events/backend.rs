@@ -1,15 +1,127 @@
+use crate::config::{Command, Key};
 use crate::input;
 use crate::input::events::{MouseDownEvent, MouseMoveEvent, MouseUpEvent};
 use crate::math::Point;
 use crate::{Context, GameState};
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::mouse::{MouseButton, MouseState};
 use sdl2::EventPump;
 
 pub fn poll_events(ctx: &mut Context, event_pump: &mut EventPump) {
     for event in event_pump.poll_iter() {
-        ctx.set_game_state(self::handle_event(&ctx, event));
+        if ctx.is_stats_visible() || ctx.is_result_visible() {
+            ctx.set_game_state(self::handle_overlay(&ctx, event));
+        } else if ctx.playback().is_some() {
+            self::handle_playback(ctx, event);
+        } else if ctx.name_entry().is_some() {
+            self::handle_name_entry(ctx, event);
+        } else if ctx.game_state() == GameState::Paused {
+            ctx.set_game_state(self::handle_paused(&ctx, event));
+        } else {
+            ctx.set_game_state(self::handle_event(&ctx, event));
+        }
+    }
+}
+
+/// Controls the playback of a replay. The board does not take input during
+/// playback: Space pauses, Right plays the next input, and Up and Down change
+/// the speed. These keys are fixed, as they share keys with the board's default
+/// bindings; only `Command::Quit` is looked up in the `KeyBindings`.
+fn handle_playback(ctx: &mut Context, event: Event) {
+    let (keycode, keymod) = match event {
+        Event::Quit { .. } => return ctx.set_game_state(GameState::Quit),
+        Event::KeyDown {
+            keycode: Some(keycode),
+            keymod,
+            ..
+        } => (keycode, keymod),
+        _ => return,
+    };
+
+    if let Some(Command::Quit) = self::command(ctx, keycode, keymod) {
+        return ctx.set_game_state(GameState::Quit);
+    }
+
+    if keycode == Keycode::Right {
+        return ctx.step_playback();
+    }
+
+    if let Some(playback) = ctx.playback_mut() {
+        match keycode {
+            Keycode::Space => playback.toggle_pause(),
+            Keycode::Up => playback.faster(),
+            Keycode::Down => playback.slower(),
+            _ => {}
+        }
+    }
+}
+
+/// Types the name of a new high score. The board does not take input until the
+/// name is saved or skipped
+fn handle_name_entry(ctx: &mut Context, event: Event) {
+    let submit = |ctx: &mut Context| {
+        if let Err(e) = ctx.submit_name_entry() {
+            eprintln!("could not save high score: {}", e);
+        }
+    };
+
+    match event {
+        Event::Quit { .. } => {
+            submit(ctx);
+            ctx.set_game_state(GameState::Quit);
+        }
+        Event::TextInput { text, .. } => {
+            if let Some(entry) = ctx.name_entry_mut() {
+                entry.push_str(&text);
+            }
+        }
+        Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } => match keycode {
+            Keycode::Return | Keycode::KpEnter => submit(ctx),
+            Keycode::Escape => ctx.set_name_entry(None),
+            Keycode::Backspace => {
+                if let Some(entry) = ctx.name_entry_mut() {
+                    entry.pop();
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Resumes the paused game on any key or mouse button, without passing the
+/// event on to the board
+fn handle_paused(ctx: &Context, event: Event) -> GameState {
+    match event {
+        Event::Quit { .. } => GameState::Quit,
+        Event::KeyDown {
+            keycode: Some(keycode),
+            keymod,
+            ..
+        } => match self::command(ctx, keycode, keymod) {
+            Some(Command::Quit) => GameState::Quit,
+            _ => input::board::on_resume(ctx),
+        },
+        Event::MouseButtonDown { .. } => input::board::on_resume(ctx),
+        _ => ctx.game_state(),
+    }
+}
+
+/// Closes the statistics screen or the result of the game on any key or mouse
+/// button, without passing the event on to the board
+fn handle_overlay(ctx: &Context, event: Event) -> GameState {
+    match event {
+        Event::Quit { .. } => GameState::Quit,
+        Event::KeyDown { .. } | Event::MouseButtonDown { .. } => {
+            ctx.set_stats_visible(false);
+            ctx.set_result_visible(false);
+            ctx.game_state()
+        }
+        _ => ctx.game_state(),
     }
 }
 
@@ -25,10 +137,16 @@ fn handle_event(ctx: &Context, event: Event) -> GameState {
         Event::MouseMotion {
             mousestate, x, y, ..
         } => self::handle_mouse_motion(ctx, mousestate, x, y),
-        Event::KeyDown { keycode, .. } => match keycode {
-            Some(k) => self::handle_key_down(ctx, k),
+        Event::KeyDown {
+            keycode, keymod, ..
+        } => match keycode {
+            Some(k) => self::handle_key_down(ctx, k, keymod),
             None => ctx.game_state(),
         },
+        Event::Window {
+            win_event: WindowEvent::FocusLost,
+            ..
+        } if ctx.config().window.pause_on_focus_loss => input::board::on_pause(ctx),
         _ => ctx.game_state(),
     }
 }
@@ -61,9 +179,46 @@ fn handle_mouse_motion(ctx: &Context, mouse_state: MouseState, x: i32, y: i32) -
     )
 }
 
-fn handle_key_down(ctx: &Context, keycode: Keycode) -> GameState {
-    match keycode {
-        Keycode::F2 => GameState::Reset,
-        _ => ctx.game_state(),
+/// Returns `Some(Command)` bound to the key of a `KeyDown` event in the
+/// `[keybindings]`, `None` if it is not bound
+fn command(ctx: &Context, keycode: Keycode, keymod: Mod) -> Option<Command> {
+    Key::from_event(keycode, keymod).and_then(|k| ctx.config().keybindings.get(k))
+}
+
+fn handle_key_down(ctx: &Context, keycode: Keycode, keymod: Mod) -> GameState {
+    let command = match self::command(ctx, keycode, keymod) {
+        Some(c) => c,
+        None => return ctx.game_state(),
+    };
+
+    match command {
+        Command::Reveal => input::board::on_reveal_cursor(ctx),
+        Command::Flag => input::board::on_flag_cursor(ctx),
+        Command::Chord => input::board::on_chord_cursor(ctx),
+        Command::Reset => GameState::Reset,
+        Command::Hint => input::board::on_hint(ctx),
+        Command::Undo => input::board::on_undo(ctx),
+        Command::Redo => input::board::on_redo(ctx),
+        Command::Save => {
+            if let Err(e) = ctx.save_game() {
+                eprintln!("could not save game: {}", e);
+            }
+            ctx.game_state()
+        }
+        Command::Heatmap => input::board::on_heatmap(ctx),
+        Command::Stats => {
+            ctx.set_stats_visible(true);
+            ctx.game_state()
+        }
+        Command::Pause => input::board::on_pause(ctx),
+        Command::Quit => GameState::Quit,
+        Command::Zoom => {
+            ctx.toggle_zoom();
+            ctx.game_state()
+        }
+        Command::CursorLeft => input::board::on_move_cursor(ctx, -1, 0),
+        Command::CursorRight => input::board::on_move_cursor(ctx, 1, 0),
+        Command::CursorUp => input::board::on_move_cursor(ctx, 0, -1),
+        Command::CursorDown => input::board::on_move_cursor(ctx, 0, 1),
     }
 }
@@ -1,8 +1,8 @@
-use crate::board::Board;
 use crate::config::{ControlConfig, LedDisplayConfig};
-use crate::control::{Button, Stopwatch};
+use crate::control::Button;
 use crate::draw::control::{DrawLedDisplay, DrawResetButtonBuilder, LedDisplayKind};
 use crate::draw::Margins;
+use crate::game::Game;
 use crate::input;
 use crate::layout::{Element, ElementBuilder, Layout, LayoutBuilder, Orientation};
 use crate::{ModelRef, MsResult};
@@ -12,8 +12,7 @@ use std::convert::TryInto;
 pub struct ControlLayout<'a> {
     config: &'a ControlConfig,
     board_width: u32,
-    board: &'a ModelRef<Board>,
-    stopwatch: &'a ModelRef<Stopwatch>,
+    game: &'a ModelRef<Game>,
     reset_button: &'a ModelRef<Button>,
 }
 
@@ -36,14 +35,18 @@ impl TryInto<Layout> for ControlLayout<'_> {
         let fc = &self.config.flag_counter;
         let sw = &self.config.stopwatch;
 
-        let btn_left = w / 2 - btn_width / 2 - fc.dimen.width() - p - fc.padding * 2;
-        let btn_right = w / 2 - btn_width / 2 - sw.dimen.width() - p - sw.padding * 2;
+        // a display wider than its half of the board pushes the reset button
+        // off center rather than underflowing
+        let btn_left =
+            (w / 2).saturating_sub(btn_width / 2 + fc.fitted_dimen().width() + p + fc.padding * 2);
+        let btn_right =
+            (w / 2).saturating_sub(btn_width / 2 + sw.fitted_dimen().width() + p + sw.padding * 2);
 
         layout.insert_all(vec![
             (
                 "flag_counter",
                 Element::new(Box::new(self::make_led_display(
-                    LedDisplayKind::FlagCounter(self.board.clone()),
+                    LedDisplayKind::FlagCounter(self.game.clone()),
                     &fc,
                 )?)),
             ),
@@ -66,7 +69,7 @@ impl TryInto<Layout> for ControlLayout<'_> {
             (
                 "stopwatch",
                 Element::new(Box::new(self::make_led_display(
-                    LedDisplayKind::Stopwatch(self.stopwatch.clone()),
+                    LedDisplayKind::Stopwatch(self.game.clone()),
                     &sw,
                 )?)),
             ),
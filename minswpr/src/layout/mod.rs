@@ -1,9 +1,9 @@
 pub mod control;
 
 use crate::app::context::Context;
-use crate::app::GameState;
 use crate::draw::{Draw, DrawContext};
 use crate::math::{Dimen, Point};
+use crate::GameState;
 use crate::MsResult;
 use sdl2::pixels::Color;
 use std::any::TypeId;
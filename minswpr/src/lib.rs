@@ -47,15 +47,32 @@ pub mod board;
 pub mod config;
 /// Defines components for the control panel above the board
 pub mod control;
+/// Reads and writes the JSON files in the user's data directory
+pub mod data;
 /// Handles font loading
 pub mod fonts;
+/// Implements the rules of the game without a window
+pub mod game;
 /// Implements the behavior for input events
 pub mod input;
 /// Handles the layout of the GUI
 pub mod layout;
+/// Records the results of finished games
+pub mod record;
+/// Records the inputs of games so that they can be played back
+pub mod replay;
+/// Saves in-progress games to disk so they can be resumed
+pub mod save;
+/// Keeps the fastest wins on each board
+pub mod scores;
+/// Implements a logical solver for the board
+pub mod solver;
+/// Tracks lifetime statistics of the games played on each board
+pub mod stats;
 
 pub use app::context::*;
 pub use app::*;
+pub use game::GameState;
 pub use model::*;
 
 /// Helper type for Result
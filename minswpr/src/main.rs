@@ -2,11 +2,34 @@ use clap::Clap;
 use minswpr::config::{self, difficulties};
 use minswpr::math::{Dimen, Point};
 use minswpr::{point, Minswpr, MsResult};
+use minswpr::{replay, scores, stats};
 use std::path::PathBuf;
 
 fn main() -> MsResult {
     let opts = Opts::parse();
 
+    match opts.subcmd {
+        Some(SubCommand::Scores) => {
+            print!("{}", scores::read(&scores::path()?)?);
+            return Ok(());
+        }
+        Some(SubCommand::Stats) => {
+            let stats = stats::read(&stats::path()?)?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&stats.to_json()).map_err(|e| e.to_string())?
+            );
+            return Ok(());
+        }
+        Some(SubCommand::Verify(v)) => {
+            let replay = replay::read(&PathBuf::from(v.replay))?;
+            let record = replay::verify(&replay).map_err(|e| format!("invalid replay: {}", e))?;
+            println!("{}", replay::verdict(&record));
+            return Ok(());
+        }
+        None => {}
+    }
+
     let config = opts
         .config
         .map(PathBuf::from)
@@ -27,12 +50,26 @@ fn main() -> MsResult {
     let num_mines = config.board.num_mines;
     config.board.num_mines = opts.num_mines.unwrap_or_else(|| num_mines);
 
+    // override config with CLI-provided seed
+    if opts.seed.is_some() {
+        config.board.seed = opts.seed;
+    }
+
+    // only boards that can be solved without guessing
+    if opts.no_guess {
+        config.board.no_guess = true;
+    }
+
     // override config with difficulty settings
     if let Some(diff) = opts.difficulty {
         difficulties::apply_to_config(&mut config, &diff)?;
     }
 
-    Minswpr::new(config)?.start()
+    let mut app = Minswpr::new(config)?;
+    match opts.replay {
+        Some(path) => app.start_replay(replay::read(&PathBuf::from(path))?),
+        None => app.start(),
+    }
 }
 
 /// A clone of Microsoft's classic Minesweeper, because why not?
@@ -55,7 +92,39 @@ struct Opts {
     /// The amount of mines to place on the board (overrides config)
     #[clap(short = "m", long = "num-mines")]
     num_mines: Option<usize>,
+    /// The seed used to place the mines, for reproducing a board (overrides
+    /// `config`)
+    #[clap(long = "seed")]
+    seed: Option<u64>,
+    /// Try to generate boards that can be cleared without guessing. This is
+    /// best-effort: if no such board is found quickly, one that may need a guess
+    /// is played and a warning is shown (overrides `config`)
+    #[clap(long = "no-guess")]
+    no_guess: bool,
     /// The difficulty mode (overrides `width`, `height`, and `num_mines`)
     #[clap(long = "difficulty", possible_values = difficulties::ALL)]
     difficulty: Option<String>,
+    /// Path to a replay file to play back instead of starting a game
+    #[clap(long = "replay")]
+    replay: Option<String>,
+    #[clap(subcommand)]
+    subcmd: Option<SubCommand>,
+}
+
+#[derive(Clap)]
+enum SubCommand {
+    /// Prints the high score table of every board and exits
+    Scores,
+    /// Prints the lifetime statistics of every board as JSON and exits
+    Stats,
+    /// Plays a replay back without a window and checks that it is consistent
+    /// with the result it claims. Exits with an error if it is not, and lists
+    /// the help used otherwise
+    Verify(Verify),
+}
+
+#[derive(Clap)]
+struct Verify {
+    /// Path to the replay file to verify
+    replay: String,
 }
@@ -9,13 +9,13 @@ use std::hash::Hash;
 use std::num::ParseIntError;
 
 /// Returns a uniform random distribution of unique values of `n` elements in
-/// the range `(lo, hi)`
-pub fn gen_rand_unique<T, B>(n: usize, lo: B, hi: B) -> Vec<T>
+/// the range `(lo, hi)`, drawn from the specified `rng`
+pub fn gen_rand_unique<T, B, R>(rng: &mut R, n: usize, lo: B, hi: B) -> Vec<T>
 where
     T: SampleUniform + Eq + Hash,
     B: SampleBorrow<T> + Copy,
+    R: Rng + ?Sized,
 {
-    let mut rng = rand::thread_rng();
     let mut res = HashSet::with_capacity(n);
     while res.len() < n {
         let v = rng.gen_range(lo, hi);
@@ -40,7 +40,7 @@ mod tests {
 
     #[test]
     fn test_gen_rand_unique() {
-        let res = super::gen_rand_unique(100, 0, 1000);
+        let res = super::gen_rand_unique(&mut rand::thread_rng(), 100, 0, 1000);
         assert_eq!(res.len(), 100);
     }
 
@@ -0,0 +1,169 @@
+use crate::board::Board;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Counts the clicks made on the board during a game. Revealing, flagging and
+/// chording with the keyboard count as the clicks they stand for.
+#[derive(new, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clicks {
+    #[new(default)]
+    pub left: usize,
+    #[new(default)]
+    pub right: usize,
+    #[new(default)]
+    pub chord: usize,
+}
+
+impl Clicks {
+    /// Returns the amount of clicks of every kind
+    pub fn total(&self) -> usize {
+        self.left + self.right + self.chord
+    }
+}
+
+/// Counts the help the player has received and the clicks they made during the
+/// current game
+#[derive(new, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameLog {
+    #[new(default)]
+    hints_used: usize,
+    #[new(default)]
+    undo_used: bool,
+    #[new(default)]
+    #[serde(default)]
+    heatmap_used: bool,
+    #[new(default)]
+    #[serde(default)]
+    clicks: Clicks,
+}
+
+impl GameLog {
+    /// Returns the amount of hints used
+    pub fn hints_used(&self) -> usize {
+        self.hints_used
+    }
+
+    /// Counts one more hint
+    pub fn add_hint(&mut self) {
+        self.hints_used += 1
+    }
+
+    /// Returns true if an action was undone during the game
+    pub fn undo_used(&self) -> bool {
+        self.undo_used
+    }
+
+    /// Marks the game as one where an action was undone
+    pub fn set_undo_used(&mut self) {
+        self.undo_used = true
+    }
+
+    /// Returns true if the mine probabilities were shown during the game
+    pub fn heatmap_used(&self) -> bool {
+        self.heatmap_used
+    }
+
+    /// Marks the game as one where the mine probabilities were shown
+    pub fn set_heatmap_used(&mut self) {
+        self.heatmap_used = true
+    }
+
+    /// Returns the `Clicks` made on the board
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    /// Returns the `Clicks` made on the board, for counting another one
+    pub fn clicks_mut(&mut self) -> &mut Clicks {
+        &mut self.clicks
+    }
+}
+
+/// The result of a finished game. Games where a hint, undo or the heatmap was
+/// used do not count toward records, see `GameRecord::is_assisted`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub did_win: bool,
+    pub width: usize,
+    pub height: usize,
+    pub num_mines: usize,
+    pub seed: u64,
+    pub elapsed: Duration,
+    pub hints_used: usize,
+    pub undo_used: bool,
+    pub heatmap_used: bool,
+    /// The 3BV of the board, see `Board::bbbv`
+    pub bbbv: usize,
+    pub clicks: Clicks,
+}
+
+impl GameRecord {
+    /// Creates a new `GameRecord` for a game played on the specified `board`
+    /// that took `elapsed` time, with the help and clicks counted in `log`
+    pub fn new(board: &Board, did_win: bool, elapsed: Duration, log: &GameLog) -> Self {
+        Self {
+            did_win,
+            width: board.width(),
+            height: board.height(),
+            num_mines: board.num_mines(),
+            seed: board.seed(),
+            elapsed,
+            hints_used: log.hints_used(),
+            undo_used: log.undo_used(),
+            heatmap_used: log.heatmap_used(),
+            bbbv: board.bbbv().unwrap_or_default(),
+            clicks: log.clicks(),
+        }
+    }
+
+    /// Creates a new `GameRecord` of a game on a board of the specified size
+    /// without help or clicks, for tests
+    #[cfg(test)]
+    pub fn new_test(
+        did_win: bool,
+        width: usize,
+        height: usize,
+        num_mines: usize,
+        elapsed: Duration,
+    ) -> Self {
+        Self {
+            did_win,
+            width,
+            height,
+            num_mines,
+            seed: 0,
+            elapsed,
+            hints_used: 0,
+            undo_used: false,
+            heatmap_used: false,
+            bbbv: 0,
+            clicks: Clicks::default(),
+        }
+    }
+
+    /// Returns true if a hint was used, an action was undone or the mine
+    /// probabilities were shown during the game. Such games are kept off the
+    /// high scores and the 3BV statistics, and reported as assisted by
+    /// `replay::verdict`.
+    pub fn is_assisted(&self) -> bool {
+        self.hints_used > 0 || self.undo_used || self.heatmap_used
+    }
+
+    /// Returns the 3BV cleared per second, which is only meaningful for a won
+    /// game
+    pub fn bbbv_per_sec(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            secs if secs > 0.0 => self.bbbv as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the 3BV per click made, from 0 up to 1 if no click was wasted,
+    /// or more if chords saved clicks. This is only meaningful for a won game.
+    pub fn efficiency(&self) -> f64 {
+        match self.clicks.total() {
+            0 => 0.0,
+            n => self.bbbv as f64 / n as f64,
+        }
+    }
+}
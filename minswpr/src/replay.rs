@@ -0,0 +1,928 @@
+use crate::board::{Board, CellFlags};
+use crate::control::{Clock, ManualClock};
+use crate::data;
+use crate::game::{Event, Game};
+use crate::math::Point;
+use crate::record::GameRecord;
+use crate::MsResult;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The version of the replay format written by this version of minswpr.
+/// Replays of a newer version are refused by `read`
+pub const VERSION: u32 = 2;
+
+/// The speeds a `Playback` can play a replay at, as multiples of the recorded
+/// speed
+pub const SPEEDS: &[f64] = &[0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// How far the times of a replay may be off when it is verified, since they are
+/// recorded in whole milliseconds
+pub const TIME_TOLERANCE: Duration = Duration::from_millis(50);
+
+/// The least time between two inputs of a replay while the stopwatch runs when
+/// it is verified. Faster inputs are taken as automated. Cursor moves are not
+/// counted, since holding a key repeats them quickly, and neither is showing
+/// the heatmap, which can be recorded along with another input.
+pub const MIN_INTERVAL: Duration = Duration::from_millis(30);
+
+/// An input of the player on the board, at the position of the cell it was
+/// given on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    Reveal(u32, u32),
+    Chord(u32, u32),
+    Flag(u32, u32),
+    /// The keyboard cursor was moved to the cell
+    Cursor(u32, u32),
+    Hint,
+    Undo,
+    Redo,
+    /// The mine probabilities were shown, see `Game::show_heatmap`
+    Heatmap,
+}
+
+impl Input {
+    /// Applies this input to the specified `game`, counting it toward the
+    /// clicks made during the game, and returns the resulting `Event`s. Moving
+    /// the cursor has no effect on the game.
+    pub fn apply(self, game: &mut Game) -> Vec<Event> {
+        // clicks on a finished board do not count toward its efficiency
+        if !game.is_over() {
+            let clicks = game.log_mut().clicks_mut();
+            match self {
+                Self::Reveal(..) => clicks.left += 1,
+                Self::Flag(..) => clicks.right += 1,
+                Self::Chord(..) => clicks.chord += 1,
+                _ => {}
+            }
+        }
+
+        match self {
+            Self::Reveal(x, y) => game.reveal(x, y),
+            Self::Chord(x, y) => game.chord(x, y),
+            Self::Flag(x, y) => game.toggle_flag(x, y),
+            Self::Cursor(..) => vec![],
+            Self::Hint => {
+                game.hint();
+                vec![]
+            }
+            Self::Undo => game.undo(),
+            Self::Redo => game.redo(),
+            Self::Heatmap => {
+                game.show_heatmap();
+                vec![]
+            }
+        }
+    }
+
+    /// Returns `Some(Point<u32>)` with the cell this input was given on, or
+    /// `None` if it is not given on a cell
+    pub fn pos(self) -> Option<Point<u32>> {
+        match self {
+            Self::Reveal(x, y) | Self::Chord(x, y) | Self::Flag(x, y) | Self::Cursor(x, y) => {
+                Some(point!(x, y))
+            }
+            Self::Hint | Self::Undo | Self::Redo | Self::Heatmap => None,
+        }
+    }
+}
+
+/// An `Input` and the time on the stopwatch in milliseconds when it was given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step(pub u64, pub Input);
+
+impl Step {
+    /// Returns the time on the stopwatch in milliseconds
+    pub fn time(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns the `Input` given
+    pub fn input(&self) -> Input {
+        self.1
+    }
+}
+
+/// A finished game with every input given during it, that can be played back
+/// or checked by playing the inputs on the same board
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub width: usize,
+    pub height: usize,
+    pub num_mines: usize,
+    pub seed: u64,
+    /// The indices of the cells with mines, row by row, since the layout also
+    /// depends on the first revealed cell and the board settings
+    pub mines: Vec<usize>,
+    /// The board settings the mines were placed with, not recorded before
+    /// version 2
+    #[serde(default)]
+    pub safe_area: bool,
+    #[serde(default)]
+    pub no_guess: bool,
+    pub question_marks: bool,
+    pub did_win: bool,
+    /// The final time on the stopwatch in milliseconds
+    pub elapsed_ms: u64,
+    pub steps: Vec<Step>,
+}
+
+impl Replay {
+    /// Creates a new `Replay` of the specified `game` with the specified
+    /// `steps`. Returns `None` if the game is not over.
+    pub fn new(game: &Game, steps: Vec<Step>) -> Option<Self> {
+        let record = game.record()?;
+        let board = game.board();
+        let mines = self::mine_indices(board);
+
+        Some(Self {
+            version: VERSION,
+            width: record.width,
+            height: record.height,
+            num_mines: record.num_mines,
+            seed: record.seed,
+            mines,
+            safe_area: game.safe_area(),
+            no_guess: game.no_guess(),
+            question_marks: board.question_marks(),
+            did_win: record.did_win,
+            elapsed_ms: record.elapsed.as_millis() as u64,
+            steps,
+        })
+    }
+}
+
+/// Plays the inputs of a `Replay` back on a `Game` at the times they were
+/// recorded. The stopwatch of the game follows the time of the replay rather
+/// than the system clock.
+pub struct Playback {
+    replay: Replay,
+    clock: ManualClock,
+    next: usize,
+    speed: usize,
+    is_paused: bool,
+}
+
+impl Playback {
+    /// Creates a new `Playback` of the specified `replay` at normal speed
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            clock: ManualClock::default(),
+            next: 0,
+            speed: 1,
+            is_paused: false,
+        }
+    }
+
+    /// Returns the `Replay` being played
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Returns a new `Game` on the board of the replay, timed by the replay.
+    /// Returns `Err(String)` if the mines of the replay do not fit its board.
+    pub fn make_game(&self) -> MsResult<Game> {
+        let r = &self.replay;
+        if r.mines.len() != r.num_mines {
+            return Err(format!(
+                "replay has {} mines but lists {}",
+                r.num_mines,
+                r.mines.len()
+            ));
+        }
+
+        let mut board = Board::with_mines(r.width, r.height, &r.mines, r.seed)?;
+        board.set_question_marks(r.question_marks);
+        Ok(Game::with_clock(
+            board,
+            false,
+            false,
+            Clock::Manual(self.clock.clone()),
+        ))
+    }
+
+    /// Returns the current time of the replay
+    pub fn time(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Returns the current speed as a multiple of the recorded speed
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    /// Plays the replay at the next faster speed in `SPEEDS`
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    /// Plays the replay at the next slower speed in `SPEEDS`
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// Returns true if the playback is paused
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// Pauses the playback or continues it if it is paused
+    pub fn toggle_pause(&mut self) {
+        self.is_paused = !self.is_paused
+    }
+
+    /// Returns true if every input of the replay has been played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.steps.len()
+    }
+
+    /// Moves the time of the replay forward by the specified real time `dt`,
+    /// scaled by the speed, and plays the inputs that are due on the specified
+    /// `game`. Returns the inputs played. This method has no effect while
+    /// paused.
+    pub fn advance(&mut self, dt: Duration, game: &mut Game) -> Vec<Input> {
+        if self.is_paused || self.is_finished() {
+            return vec![];
+        }
+
+        let time = self.clock.now() + dt.mul_f64(self.speed());
+        let mut inputs = vec![];
+        while self.next_time().filter(|t| *t <= time).is_some() {
+            inputs.extend(self.step(game));
+        }
+
+        self.clock.set(time);
+        inputs
+    }
+
+    /// Plays the next input on the specified `game` right away, moving the
+    /// time of the replay forward to when it was given. Returns `None` if the
+    /// replay is finished.
+    pub fn step(&mut self, game: &mut Game) -> Option<Input> {
+        let step = *self.replay.steps.get(self.next)?;
+        self.next += 1;
+
+        let time = Duration::from_millis(step.time());
+        if time > self.clock.now() {
+            self.clock.set(time);
+        }
+
+        step.input().apply(game);
+        Some(step.input())
+    }
+
+    fn next_time(&self) -> Option<Duration> {
+        self.replay
+            .steps
+            .get(self.next)
+            .map(|s| Duration::from_millis(s.time()))
+    }
+}
+
+/// Prints the state of the playback, like `replay at 2x (paused)`
+impl fmt::Display for Playback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_finished() {
+            return write!(f, "replay finished");
+        }
+
+        write!(f, "replay at {}x", self.speed())?;
+        if self.is_paused {
+            write!(f, " (paused)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the inputs given during a game, timed by the stopwatch of the game
+pub struct Recorder {
+    steps: Vec<Step>,
+    is_enabled: bool,
+    created: u64,
+}
+
+impl Recorder {
+    /// Creates a new `Recorder` for a new game. No inputs are recorded if
+    /// `is_enabled` is false, like for games resumed from a save, whose earlier
+    /// inputs are not known.
+    pub fn new(is_enabled: bool) -> Self {
+        Self {
+            steps: vec![],
+            is_enabled,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns true if inputs are recorded
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    /// Returns the inputs recorded so far
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Records the specified `input` at the current time on the stopwatch of
+    /// the specified `game`. Inputs other than undo are not recorded once the
+    /// game is over, since they have no effect.
+    pub fn record(&mut self, game: &Game, input: Input) {
+        if self.is_enabled && (!game.is_over() || input == Input::Undo) {
+            let time = game.stopwatch().elapsed().as_millis() as u64;
+            self.steps.push(Step(time, input));
+        }
+    }
+
+    /// Returns `Some(Replay)` of the specified `game` with the inputs recorded
+    /// so far. Returns `None` if recording is disabled or the game is not
+    /// over.
+    pub fn replay(&self, game: &Game) -> Option<Replay> {
+        if self.is_enabled {
+            Replay::new(game, self.steps.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the name of the replay file of the game, which stays the same if
+    /// the game ends more than once after undoing
+    pub fn file_name(&self, game: &Game) -> String {
+        format!("{}-{}.json", self.created, game.board().seed())
+    }
+}
+
+/// Plays the specified `replay` back on a new board without a window and checks
+/// that it is consistent with the result it claims: the mines are the ones the
+/// seed and board settings place for the first revealed cell, every input is
+/// given on the board while the game is not over and no sooner than
+/// `MIN_INTERVAL` after the one before it, no time passes while the stopwatch
+/// is stopped, and the game ends with the claimed result at the claimed time.
+/// Returns the `GameRecord` of the replayed game, or an `Err(String)`
+/// describing the first inconsistency found.
+///
+/// Replays before version 2 do not record the board settings, so they can not
+/// be verified.
+pub fn verify(replay: &Replay) -> MsResult<GameRecord> {
+    if replay.version < 2 {
+        return Err(format!(
+            "replays of version {} do not record the board settings",
+            replay.version
+        ));
+    }
+
+    let mut playback = Playback::new(replay.clone());
+    let mut game = playback.make_game()?;
+    let mut last_time = 0;
+    let mut last_input = None;
+
+    // the same inputs on a board whose mines are not placed yet, to place them
+    // the way the game would have
+    let mut deferred = Game::new(
+        Board::new_deferred(replay.width, replay.height, replay.num_mines, replay.seed)?,
+        replay.safe_area,
+        replay.no_guess,
+    );
+    let mut mines = replay.mines.clone();
+    mines.sort_unstable();
+
+    for (i, step) in replay.steps.iter().enumerate() {
+        let time = Duration::from_millis(step.time());
+        let fail = |reason: &str| {
+            Err(format!(
+                "step {} at {:.3} seconds: {}",
+                i + 1,
+                time.as_secs_f64(),
+                reason
+            ))
+        };
+
+        if step.time() < last_time {
+            return fail("recorded before the step preceding it");
+        }
+        last_time = step.time();
+
+        if let Some(Point { x, y }) = step.input().pos() {
+            if x as usize >= replay.width || y as usize >= replay.height {
+                return fail(&format!("({}, {}) is not on the board", x, y));
+            }
+        }
+
+        // only an undo can continue a game that is over
+        if game.is_over() && step.input() != Input::Undo {
+            return fail("the game is already over");
+        }
+
+        let stopwatch = game.stopwatch();
+        if !stopwatch.is_running() && time > stopwatch.elapsed() + TIME_TOLERANCE {
+            return fail("time passed while the stopwatch was stopped");
+        }
+
+        if !matches!(step.input(), Input::Cursor(..) | Input::Heatmap) {
+            if stopwatch.is_running() && last_input.filter(|t| time < *t + MIN_INTERVAL).is_some() {
+                return fail("given too soon after the input before it");
+            }
+            last_input = Some(time);
+        }
+
+        if !deferred.board().is_generated() {
+            step.input().apply(&mut deferred);
+            if deferred.board().is_generated() && self::mine_indices(deferred.board()) != mines {
+                return fail("the mines are not the ones the seed places");
+            }
+        }
+
+        playback.step(&mut game);
+    }
+
+    let record = game
+        .record()
+        .ok_or_else(|| "the game does not end".to_string())?;
+
+    if record.did_win != replay.did_win {
+        return Err(format!(
+            "the game was {} but the replay claims it was {}",
+            if record.did_win { "won" } else { "lost" },
+            if replay.did_win { "won" } else { "lost" }
+        ));
+    }
+
+    let claimed = Duration::from_millis(replay.elapsed_ms);
+    let diff = cmp::max(claimed, record.elapsed) - cmp::min(claimed, record.elapsed);
+    if diff > TIME_TOLERANCE {
+        return Err(format!(
+            "the game took {:.3} seconds but the replay claims {:.3} seconds",
+            record.elapsed.as_secs_f64(),
+            claimed.as_secs_f64()
+        ));
+    }
+
+    Ok(record)
+}
+
+/// Describes the result of a replay that passed `verify` with the `record` it
+/// returned. A valid replay only shows that the game was played the way it
+/// claims, so games where hints, undo or the heatmap were used get a distinct
+/// verdict listing the help, see `GameRecord::is_assisted`.
+pub fn verdict(record: &GameRecord) -> String {
+    let result = format!(
+        "{} in {:.3} seconds",
+        if record.did_win { "won" } else { "lost" },
+        record.elapsed.as_secs_f64()
+    );
+
+    let mut help = vec![];
+    match record.hints_used {
+        0 => {}
+        1 => help.push("1 hint used".to_string()),
+        n => help.push(format!("{} hints used", n)),
+    }
+    if record.undo_used {
+        help.push("undo used".to_string());
+    }
+    if record.heatmap_used {
+        help.push("heatmap used".to_string());
+    }
+
+    if record.is_assisted() {
+        format!("valid assisted replay: {} ({})", result, help.join(", "))
+    } else {
+        format!("valid replay: {}", result)
+    }
+}
+
+/// Returns the indices of the cells with mines on the specified `board`, row by
+/// row
+fn mine_indices(board: &Board) -> Vec<usize> {
+    board
+        .cells()
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.contains(CellFlags::MINE))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the directory replays are written to, see `data::path`
+pub fn dir() -> MsResult<PathBuf> {
+    data::path("replays")
+}
+
+/// Writes the specified `replay` to the file at `path`
+pub fn write(replay: &Replay, path: &Path) -> MsResult {
+    data::write(replay, path, false)
+}
+
+/// Reads the `Replay` in the file at `path`. Returns an `Err(String)` if the
+/// file could not be read or was written by a newer version of minswpr.
+pub fn read(path: &Path) -> MsResult<Replay> {
+    let replay: Replay = data::read(path, "replay")?
+        .ok_or_else(|| format!("could not read replay `{}`: no such file", path.display()))?;
+
+    if replay.version > VERSION {
+        return Err(format!(
+            "replay `{}` has version {}, but only versions up to {} are supported",
+            path.display(),
+            replay.version,
+            VERSION
+        ));
+    }
+
+    Ok(replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, Playback, Recorder, Replay, Step, MIN_INTERVAL, TIME_TOLERANCE, VERSION};
+    use crate::board::{Board, CellFlags};
+    use crate::control::{Clock, ManualClock};
+    use crate::data;
+    use crate::game::{Game, GameState};
+    use std::time::Duration;
+
+    fn make_replay() -> Replay {
+        Replay {
+            version: VERSION,
+            width: 3,
+            height: 3,
+            num_mines: 2,
+            // places these mines when (2, 0) is revealed first
+            seed: 65,
+            mines: vec![0, 8],
+            safe_area: false,
+            no_guess: false,
+            question_marks: false,
+            did_win: true,
+            elapsed_ms: 4_000,
+            steps: vec![
+                Step(0, Input::Reveal(2, 0)),
+                Step(1_500, Input::Flag(0, 0)),
+                Step(4_000, Input::Reveal(0, 2)),
+            ],
+        }
+    }
+
+    fn make_playback() -> Playback {
+        Playback::new(make_replay())
+    }
+
+    #[test]
+    fn test_record() {
+        let mut game = Game::new(
+            "*..
+             ...
+             ..*"
+            .parse()
+            .unwrap(),
+            true,
+            false,
+        );
+        let mut recorder = Recorder::new(true);
+
+        let inputs = [Input::Cursor(1, 0), Input::Flag(0, 0), Input::Reveal(2, 0)];
+        for input in &inputs {
+            recorder.record(&game, *input);
+            input.apply(&mut game);
+        }
+
+        // only finished games have a replay
+        assert!(recorder.replay(&game).is_none());
+
+        recorder.record(&game, Input::Reveal(0, 2));
+        Input::Reveal(0, 2).apply(&mut game);
+        assert!(game.is_over());
+
+        let replay = recorder.replay(&game).unwrap();
+        assert_eq!(VERSION, replay.version);
+        assert_eq!(vec![0, 8], replay.mines);
+        assert!(replay.safe_area);
+        assert!(!replay.no_guess);
+        assert!(replay.did_win);
+        assert_eq!(4, replay.steps.len());
+        assert_eq!(Step(0, Input::Cursor(1, 0)), replay.steps[0]);
+
+        let json = serde_json::to_string(&replay).unwrap();
+        assert!(json.contains(r#"[0,{"cursor":[1,0]}]"#), "{}", json);
+
+        let loaded = data::round_trip("replay", &replay, super::write, super::read).unwrap();
+        assert_eq!(replay, loaded);
+    }
+
+    #[test]
+    fn test_read_newer_version() {
+        let mut game = Game::new("*.".parse().unwrap(), false, false);
+        let mut recorder = Recorder::new(true);
+        recorder.record(&game, Input::Reveal(1, 0));
+        Input::Reveal(1, 0).apply(&mut game);
+
+        let mut replay = recorder.replay(&game).unwrap();
+        replay.version = VERSION + 1;
+
+        let err = data::round_trip("replay-version", &replay, super::write, super::read)
+            .err()
+            .unwrap();
+        assert!(err.contains("only versions up to"), "{}", err);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut game = Game::new("*.".parse().unwrap(), false, false);
+        let mut recorder = Recorder::new(false);
+        recorder.record(&game, Input::Reveal(1, 0));
+        Input::Reveal(1, 0).apply(&mut game);
+
+        assert!(game.is_over());
+        assert!(recorder.steps().is_empty());
+        assert!(recorder.replay(&game).is_none());
+    }
+
+    #[test]
+    fn test_playback_advance() {
+        let mut playback = make_playback();
+        let mut game = playback.make_game().unwrap();
+        let ms = Duration::from_millis;
+
+        let inputs = playback.advance(ms(1_000), &mut game);
+        assert_eq!(vec![Input::Reveal(2, 0)], inputs);
+        assert_eq!(ms(1_000), game.stopwatch().elapsed());
+
+        playback.faster();
+        assert_eq!(2.0, playback.speed());
+        assert_eq!(
+            vec![Input::Flag(0, 0)],
+            playback.advance(ms(1_000), &mut game)
+        );
+        assert_eq!(ms(3_000), game.stopwatch().elapsed());
+
+        playback.toggle_pause();
+        assert_eq!("replay at 2x (paused)", playback.to_string());
+        assert!(playback.advance(ms(1_000), &mut game).is_empty());
+        assert_eq!(ms(3_000), game.stopwatch().elapsed());
+
+        playback.toggle_pause();
+        assert_eq!(
+            vec![Input::Reveal(0, 2)],
+            playback.advance(ms(1_000), &mut game)
+        );
+        assert_eq!(GameState::Over(true), game.state());
+        assert_eq!(ms(4_000), game.stopwatch().elapsed());
+        assert!(playback.is_finished());
+        assert_eq!("replay finished", playback.to_string());
+    }
+
+    #[test]
+    fn test_playback_step() {
+        let mut playback = make_playback();
+        let mut game = playback.make_game().unwrap();
+
+        // stepping does not wait for the recorded time
+        assert_eq!(Some(Input::Reveal(2, 0)), playback.step(&mut game));
+        assert_eq!(Some(Input::Flag(0, 0)), playback.step(&mut game));
+        assert_eq!(Duration::from_millis(1_500), game.stopwatch().elapsed());
+        assert_eq!(Some(Input::Reveal(0, 2)), playback.step(&mut game));
+        assert_eq!(None, playback.step(&mut game));
+
+        for _ in 0..10 {
+            playback.slower();
+        }
+        assert_eq!(0.5, playback.speed());
+        for _ in 0..10 {
+            playback.faster();
+        }
+        assert_eq!(8.0, playback.speed());
+    }
+
+    #[test]
+    fn test_playback_bad_mines() {
+        let mut replay = make_playback().replay().clone();
+        replay.mines = vec![0];
+        assert!(Playback::new(replay.clone()).make_game().is_err());
+
+        replay.mines = vec![0, 9];
+        assert!(Playback::new(replay).make_game().is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let record = super::verify(&make_replay()).unwrap();
+        assert!(record.did_win);
+        assert_eq!(Duration::from_millis(4_000), record.elapsed);
+        assert_eq!(
+            "valid replay: won in 4.000 seconds",
+            super::verdict(&record)
+        );
+
+        let verify = |edit: fn(&mut Replay)| {
+            let mut replay = make_replay();
+            edit(&mut replay);
+            super::verify(&replay).err().unwrap()
+        };
+
+        let err = verify(|r| r.did_win = false);
+        assert!(err.contains("claims it was lost"), "{}", err);
+
+        let err = verify(|r| r.elapsed_ms = 3_000);
+        assert!(err.contains("claims 3.000 seconds"), "{}", err);
+
+        let err = verify(|r| r.steps[1] = Step(5_000, Input::Flag(0, 0)));
+        assert!(err.starts_with("step 3"), "{}", err);
+
+        let err = verify(|r| r.steps[1] = Step(1_500, Input::Flag(3, 0)));
+        assert!(err.contains("not on the board"), "{}", err);
+
+        let err = verify(|r| r.steps.push(Step(4_000, Input::Flag(1, 2))));
+        assert!(err.contains("already over"), "{}", err);
+
+        let err = verify(|r| r.steps.truncate(2));
+        assert!(err.contains("does not end"), "{}", err);
+
+        // the stopwatch does not run before the first reveal
+        let err = verify(|r| r.steps[0] = Step(1_000, Input::Reveal(2, 0)));
+        assert!(err.contains("stopwatch was stopped"), "{}", err);
+
+        // times rounded to milliseconds are close enough
+        let mut replay = make_replay();
+        replay.elapsed_ms += TIME_TOLERANCE.as_millis() as u64;
+        assert!(super::verify(&replay).is_ok());
+
+        let err = verify(|r| r.version = 1);
+        assert!(err.contains("do not record the board settings"), "{}", err);
+    }
+
+    #[test]
+    fn test_verify_recorded() {
+        let clock = ManualClock::default();
+        let board = Board::new_deferred(9, 9, 10, 1234).unwrap();
+        let mut game = Game::with_clock(board, true, true, Clock::Manual(clock.clone()));
+        let mut recorder = Recorder::new(true);
+        let mut play = |game: &mut Game, input: Input| {
+            recorder.record(game, input);
+            input.apply(game);
+        };
+
+        play(&mut game, Input::Reveal(4, 4));
+        let i = game
+            .board()
+            .cells()
+            .iter()
+            .position(|c| c.contains(CellFlags::MINE))
+            .unwrap() as u32;
+        clock.set(Duration::from_secs(1));
+        play(&mut game, Input::Reveal(i % 9, i / 9));
+
+        let replay = recorder.replay(&game).unwrap();
+        assert!(replay.safe_area && replay.no_guess);
+        let record = super::verify(&replay).unwrap();
+        assert!(!record.did_win);
+    }
+
+    #[test]
+    fn test_replay_clicks() {
+        let clock = ManualClock::default();
+        // places mines on (0, 0) and (2, 2) like `make_replay`
+        let board = Board::new_deferred(3, 3, 2, 65).unwrap();
+        let mut game = Game::with_clock(board, false, false, Clock::Manual(clock.clone()));
+        let mut recorder = Recorder::new(true);
+
+        // revealing the first cell again is a wasted click
+        let inputs = [
+            Input::Reveal(2, 0),
+            Input::Reveal(2, 0),
+            Input::Flag(0, 0),
+            Input::Chord(1, 0),
+            Input::Reveal(0, 2),
+        ];
+        for (i, input) in inputs.iter().enumerate() {
+            clock.set(Duration::from_secs(i as u64));
+            recorder.record(&game, *input);
+            input.apply(&mut game);
+        }
+
+        let record = game.record().unwrap();
+        assert!(record.did_win);
+        assert_eq!(5, record.clicks.total());
+        assert!(record.efficiency() > 0.0);
+
+        let replay = recorder.replay(&game).unwrap();
+        let mut playback = Playback::new(replay.clone());
+        let mut played = playback.make_game().unwrap();
+        while playback.step(&mut played).is_some() {}
+
+        for r in &[played.record().unwrap(), super::verify(&replay).unwrap()] {
+            assert_eq!(record.clicks, r.clicks);
+            assert_eq!(record.efficiency(), r.efficiency());
+        }
+    }
+
+    #[test]
+    fn test_verify_forged_mines() {
+        let verify = |edit: fn(&mut Replay)| {
+            let mut replay = make_replay();
+            edit(&mut replay);
+            super::verify(&replay).err().unwrap()
+        };
+
+        // mines that make the game easier than the seed would have
+        let err = verify(|r| r.mines = vec![0, 1]);
+        assert!(err.starts_with("step 1"), "{}", err);
+        assert!(err.contains("not the ones the seed places"), "{}", err);
+
+        // a seed that does not place the mines played on
+        let err = verify(|r| r.seed = 0);
+        assert!(err.contains("not the ones the seed places"), "{}", err);
+
+        // dimensions too large to allocate, or to multiply
+        let err = verify(|r| r.width = usize::MAX);
+        assert!(err.contains("must not have more than"), "{}", err);
+        let err = verify(|r| r.height = Board::MAX_CELLS);
+        assert!(err.contains("must not have more than"), "{}", err);
+    }
+
+    #[test]
+    fn test_verify_forged_interval() {
+        let mut replay = make_replay();
+        let interval = MIN_INTERVAL.as_millis() as u64;
+        replay.steps[1] = Step(interval - 1, Input::Flag(0, 0));
+        let err = super::verify(&replay).err().unwrap();
+        assert!(err.starts_with("step 2"), "{}", err);
+        assert!(err.contains("too soon"), "{}", err);
+
+        // inputs before the stopwatch starts and cursor moves can be quicker
+        let mut replay = make_replay();
+        replay.steps.insert(0, Step(0, Input::Hint));
+        replay.steps.insert(2, Step(1, Input::Cursor(1, 1)));
+        replay.steps[3] = Step(interval, Input::Flag(0, 0));
+        assert!(super::verify(&replay).is_ok());
+    }
+
+    #[test]
+    fn test_verify_undo() {
+        let mut replay = make_replay();
+        replay.steps[2] = Step(4_000, Input::Reveal(2, 2));
+        replay.steps.push(Step(4_000, Input::Undo));
+        replay.steps.push(Step(6_000, Input::Reveal(0, 2)));
+        replay.elapsed_ms = 6_000;
+
+        let record = super::verify(&replay).unwrap();
+        assert!(record.did_win);
+        assert!(record.undo_used);
+        assert_eq!(
+            "valid assisted replay: won in 6.000 seconds (undo used)",
+            super::verdict(&record)
+        );
+    }
+
+    #[test]
+    fn test_verify_hint() {
+        let mut replay = make_replay();
+        replay.steps.insert(1, Step(1_000, Input::Hint));
+
+        let record = super::verify(&replay).unwrap();
+        assert_eq!(1, record.hints_used);
+        assert_eq!(
+            "valid assisted replay: won in 4.000 seconds (1 hint used)",
+            super::verdict(&record)
+        );
+    }
+
+    #[test]
+    fn test_verify_heatmap() {
+        let mut replay = make_replay();
+        replay.steps.insert(1, Step(0, Input::Heatmap));
+
+        let record = super::verify(&replay).unwrap();
+        assert!(record.did_win);
+        assert!(record.heatmap_used);
+        assert!(!super::verify(&make_replay()).unwrap().heatmap_used);
+        assert_eq!(
+            "valid assisted replay: won in 4.000 seconds (heatmap used)",
+            super::verdict(&record)
+        );
+    }
+
+    #[test]
+    fn test_record_after_over() {
+        let mut game = Game::new("*.".parse().unwrap(), false, false);
+        let mut recorder = Recorder::new(true);
+        recorder.record(&game, Input::Reveal(0, 0));
+        Input::Reveal(0, 0).apply(&mut game);
+
+        recorder.record(&game, Input::Cursor(1, 0));
+        recorder.record(&game, Input::Undo);
+        let inputs = recorder
+            .steps()
+            .iter()
+            .map(|s| s.input())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![Input::Reveal(0, 0), Input::Undo], inputs);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::board::Board;
+use crate::config::difficulties;
+use crate::control::Stopwatch;
+use crate::data;
+use crate::math::Point;
+use crate::record::GameLog;
+use crate::MsResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An in-progress game that can be written to disk and resumed later
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    /// The name of the difficulty the game was played on, if any
+    pub difficulty: Option<String>,
+    pub board: Board,
+    pub stopwatch: Stopwatch,
+    pub game_log: GameLog,
+    /// True if the game was paused when it was saved
+    #[serde(default)]
+    pub is_paused: bool,
+}
+
+impl SaveGame {
+    /// Creates a new `SaveGame` of the game being played on the specified
+    /// `board`, which is resumed paused if `is_paused` is true
+    pub fn new(board: Board, stopwatch: Stopwatch, game_log: GameLog, is_paused: bool) -> Self {
+        let dimen = point!(board.width(), board.height());
+        Self {
+            difficulty: difficulties::find(dimen, board.num_mines()).map(String::from),
+            board,
+            stopwatch,
+            game_log,
+            is_paused,
+        }
+    }
+}
+
+/// Returns the path of the save file, see `data::path`
+pub fn path() -> MsResult<PathBuf> {
+    data::path("save.json")
+}
+
+/// Writes the specified `save` to the file at `path`
+pub fn write(save: &SaveGame, path: &Path) -> MsResult {
+    data::write(save, path, false)
+}
+
+/// Reads the `SaveGame` in the file at `path`. Returns `Ok(None)` if there is
+/// no such file or an `Err(String)` if the file could not be read.
+pub fn read(path: &Path) -> MsResult<Option<SaveGame>> {
+    data::read(path, "save file")
+}
+
+/// Removes the file at `path` if it exists
+pub fn remove(path: &Path) -> MsResult {
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| e.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SaveGame;
+    use crate::board::Board;
+    use crate::control::Stopwatch;
+    use crate::data::{self, TempFile};
+    use crate::record::GameLog;
+    use std::time::Duration;
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut board = Board::new_deferred(9, 9, 10, 1234).unwrap();
+        board.generate(4, 4, true, false);
+        board.reveal_from(4, 4);
+        board.toggle_flag(0, 0);
+
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.start();
+        stopwatch.stop();
+
+        let mut game_log = GameLog::new();
+        game_log.add_hint();
+
+        let save = SaveGame::new(board.clone(), stopwatch.clone(), game_log, true);
+        assert_eq!(Some("beginner"), save.difficulty.as_deref());
+
+        let loaded = data::round_trip("save", &save, super::write, super::read)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(board.to_string(), loaded.board.to_string());
+        assert_eq!(board.cells(), loaded.board.cells());
+        assert_eq!(1234, loaded.board.seed());
+        assert!(loaded.board.is_generated());
+        assert_eq!(stopwatch.elapsed(), loaded.stopwatch.elapsed());
+        assert_eq!(1, loaded.game_log.hints_used());
+        assert!(loaded.is_paused);
+    }
+
+    #[test]
+    fn test_remove_save() {
+        let file = TempFile::new("save-remove");
+        assert!(super::remove(file.path()).is_ok());
+
+        let board = Board::new_deferred(9, 9, 10, 0).unwrap();
+        let save = SaveGame::new(board, Stopwatch::new(), GameLog::new(), false);
+        super::write(&save, file.path()).unwrap();
+        super::remove(file.path()).unwrap();
+        assert!(super::read(file.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_stopwatch_resume() {
+        let mut s: Stopwatch = serde_json::from_str(r#"{"secs":5,"nanos":0}"#).unwrap();
+        assert_eq!(Duration::from_secs(5), s.elapsed());
+        s.resume();
+        assert!(s.elapsed() >= Duration::from_secs(5));
+    }
+}
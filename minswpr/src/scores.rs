@@ -0,0 +1,241 @@
+use crate::config::difficulties;
+use crate::data;
+use crate::math::Point;
+use crate::record::GameRecord;
+use crate::MsResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The amount of scores kept for each board
+pub const MAX_SCORES: usize = 10;
+/// The maximum amount of characters in the name of a `Score`
+pub const MAX_NAME_LEN: usize = 16;
+
+/// A single win in the high score table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub name: String,
+    pub elapsed: Duration,
+    pub seed: u64,
+}
+
+/// The fastest wins on each board. Boards of a pre-defined difficulty are
+/// ranked by the name of the difficulty, custom boards by their dimensions and
+/// amount of mines.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    boards: BTreeMap<String, Vec<Score>>,
+}
+
+impl HighScores {
+    /// Returns the scores of the board with the specified `name`, fastest
+    /// first
+    pub fn get(&self, name: &str) -> &[Score] {
+        self.boards.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns `Some(usize)` with the zero-based rank the specified `record`
+    /// would take in the table of its board. Returns `None` if the game was
+    /// lost or assisted, see `GameRecord::is_assisted`, or the time is not fast
+    /// enough.
+    pub fn rank(&self, record: &GameRecord) -> Option<usize> {
+        if !record.did_win || record.is_assisted() {
+            return None;
+        }
+
+        let scores = self.get(&self::record_board_name(record));
+        let rank = scores
+            .iter()
+            .take_while(|s| s.elapsed <= record.elapsed)
+            .count();
+        if rank < MAX_SCORES {
+            Some(rank)
+        } else {
+            None
+        }
+    }
+
+    /// Adds the specified `record` to the table of its board under the
+    /// specified `name` if it is fast enough. Returns the rank of the new
+    /// score like `HighScores::rank`.
+    pub fn insert(&mut self, record: &GameRecord, name: &str) -> Option<usize> {
+        let rank = self.rank(record)?;
+        let scores = self
+            .boards
+            .entry(self::record_board_name(record))
+            .or_default();
+
+        scores.insert(
+            rank,
+            Score {
+                name: name.chars().take(MAX_NAME_LEN).collect(),
+                elapsed: record.elapsed,
+                seed: record.seed,
+            },
+        );
+        scores.truncate(MAX_SCORES);
+
+        Some(rank)
+    }
+
+    /// Returns true if no score has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+}
+
+/// Prints the table of every board, pre-defined difficulties first
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no high scores yet");
+        }
+
+        let presets = difficulties::ALL.iter().copied();
+        let custom = self
+            .boards
+            .keys()
+            .map(String::as_str)
+            .filter(|b| !difficulties::ALL.contains(b));
+
+        let mut first = true;
+        for board in presets.chain(custom) {
+            let scores = self.get(board);
+            if scores.is_empty() {
+                continue;
+            }
+
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+
+            writeln!(f, "{}", board)?;
+            for (i, s) in scores.iter().enumerate() {
+                writeln!(
+                    f,
+                    "{:>3}. {:<width$} {:>9.3}s  seed {}",
+                    i + 1,
+                    s.name,
+                    s.elapsed.as_secs_f64(),
+                    s.seed,
+                    width = MAX_NAME_LEN
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name scores on a board with the specified dimensions and amount
+/// of mines are ranked under: the name of its difficulty, or its dimensions
+/// and amount of mines if it is a custom board
+pub fn board_name(width: usize, height: usize, num_mines: usize) -> String {
+    difficulties::find(point!(width, height), num_mines)
+        .map(String::from)
+        .unwrap_or_else(|| format!("{}x{} with {} mines", width, height, num_mines))
+}
+
+/// Returns the `board_name` of the board the specified `record` was played on
+pub fn record_board_name(record: &GameRecord) -> String {
+    self::board_name(record.width, record.height, record.num_mines)
+}
+
+/// Returns the path of the high score file, see `data::path`
+pub fn path() -> MsResult<PathBuf> {
+    data::path("scores.json")
+}
+
+/// Writes the specified `scores` to the file at `path`
+pub fn write(scores: &HighScores, path: &Path) -> MsResult {
+    data::write(scores, path, true)
+}
+
+/// Reads the `HighScores` in the file at `path`. Returns empty `HighScores` if
+/// there is no such file or an `Err(String)` if the file could not be read.
+pub fn read(path: &Path) -> MsResult<HighScores> {
+    data::read(path, "high score file").map(Option::unwrap_or_default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HighScores, MAX_SCORES};
+    use crate::data::{self, TempFile};
+    use crate::record::GameRecord;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rank() {
+        let won = |millis| GameRecord::new_test(true, 9, 9, 10, Duration::from_millis(millis));
+        let mut scores = HighScores::default();
+        assert_eq!(Some(0), scores.insert(&won(5_000), "a"));
+        assert_eq!(Some(0), scores.insert(&won(4_500), "b"));
+        assert_eq!(Some(2), scores.insert(&won(5_000), "c"));
+
+        let names = scores
+            .get("beginner")
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["b", "a", "c"], names);
+
+        // custom boards are ranked separately
+        let custom = GameRecord::new_test(true, 9, 9, 11, Duration::from_millis(9_000));
+        assert_eq!(Some(0), scores.insert(&custom, "d"));
+        assert_eq!(1, scores.get("9x9 with 11 mines").len());
+
+        let mut lost = won(1_000);
+        lost.did_win = false;
+        assert_eq!(None, scores.rank(&lost));
+
+        let mut undone = won(1_000);
+        undone.undo_used = true;
+        assert_eq!(None, scores.rank(&undone));
+
+        let mut shown = won(1_000);
+        shown.heatmap_used = true;
+        assert_eq!(None, scores.rank(&shown));
+
+        let mut hinted = won(1_000);
+        hinted.hints_used = 1;
+        assert_eq!(None, scores.rank(&hinted));
+    }
+
+    #[test]
+    fn test_max_scores() {
+        let won = |millis| GameRecord::new_test(true, 16, 16, 40, Duration::from_millis(millis));
+        let mut scores = HighScores::default();
+        for i in 0..MAX_SCORES as u64 {
+            scores.insert(&won(10_000 + i), "a");
+        }
+
+        assert_eq!(None, scores.rank(&won(20_000)));
+        assert_eq!(Some(0), scores.insert(&won(100), "b"));
+
+        let intermediate = scores.get("intermediate");
+        assert_eq!(MAX_SCORES, intermediate.len());
+        assert_eq!(Duration::from_millis(100), intermediate[0].elapsed);
+        assert_eq!(
+            Duration::from_millis(10_000 + MAX_SCORES as u64 - 2),
+            intermediate[MAX_SCORES - 1].elapsed
+        );
+    }
+
+    #[test]
+    fn test_scores_round_trip() {
+        let mut scores = HighScores::default();
+        let expert = GameRecord::new_test(true, 30, 16, 99, Duration::from_millis(123_456));
+        scores.insert(&expert, "somebody");
+
+        let loaded = data::round_trip("scores", &scores, super::write, super::read).unwrap();
+        assert_eq!(scores, loaded);
+        assert!(loaded.to_string().contains("123.456s"));
+
+        let missing = TempFile::new("scores-missing");
+        assert!(super::read(missing.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,404 @@
+mod probability;
+#[cfg(test)]
+mod tests;
+
+pub use self::probability::*;
+
+use crate::board::{Board, CellFlags};
+use crate::math::Point;
+use crate::MsResult;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// A cell as seen by the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewCell {
+    /// A cell that has not been revealed or flagged
+    Hidden,
+    /// A cell the player believes to be a mine
+    Flagged,
+    /// A revealed cell with the number of adjacent mines
+    Revealed(u8),
+}
+
+/// A view of a `Board` restricted to what the player knows: the revealed
+/// numbers, the flags and the total number of mines. Flags are assumed to be
+/// correct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    width: usize,
+    height: usize,
+    num_mines: usize,
+    cells: Vec<ViewCell>,
+}
+
+impl View {
+    /// Creates a new `View` with the specified cell `width`, `height`, total
+    /// number of mines `num_mines` and row-major `cells`. Returns `Ok(View)` if
+    /// successful, `Err(String)` otherwise.
+    pub fn new(
+        width: usize,
+        height: usize,
+        num_mines: usize,
+        cells: Vec<ViewCell>,
+    ) -> MsResult<Self> {
+        if cells.len() != width * height {
+            return Err("the number of cells must equal the area of the view".to_string());
+        }
+
+        Ok(Self {
+            width,
+            height,
+            num_mines,
+            cells,
+        })
+    }
+
+    /// Returns the cell width of the view
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the cell height of the view
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the total number of mines
+    pub fn num_mines(&self) -> usize {
+        self.num_mines
+    }
+
+    /// Returns the cell at the specified `x` and `y` position. Panics if the
+    /// cell is not present
+    pub fn cell(&self, x: u32, y: u32) -> ViewCell {
+        self.cells[self.index(x, y)]
+    }
+
+    /// Flags every cell that the specified `Deductions` proved to be a mine
+    pub fn apply(&mut self, deductions: &Deductions) {
+        for p in deductions.mines().keys() {
+            let i = self.index(p.x, p.y);
+            self.cells[i] = ViewCell::Flagged;
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    fn point(&self, i: usize) -> Point<u32> {
+        point!((i % self.width) as u32, (i / self.width) as u32)
+    }
+
+    fn neighbors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = ((i % self.width) as i64, (i / self.width) as i64);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(move |(nx, ny)| !(*nx == x && *ny == y))
+            .filter(move |(nx, ny)| {
+                *nx >= 0 && *ny >= 0 && *nx < self.width as i64 && *ny < self.height as i64
+            })
+            .map(move |(nx, ny)| ny as usize * self.width + nx as usize)
+    }
+}
+
+impl From<&Board> for View {
+    /// Creates a `View` of what the player knows about the `board`. A revealed
+    /// mine is seen as a flag.
+    fn from(board: &Board) -> Self {
+        let mut cells = Vec::with_capacity(board.cells().len());
+        for y in 0..board.height() as u32 {
+            for x in 0..board.width() as u32 {
+                let c = board.cell(x, y);
+                cells.push(
+                    if c.contains(CellFlags::REVEALED) && c.contains(CellFlags::MINE) {
+                        ViewCell::Flagged
+                    } else if c.contains(CellFlags::REVEALED) {
+                        ViewCell::Revealed(board.count_adjacent_mines(x, y) as u8)
+                    } else if c.contains(CellFlags::FLAG) {
+                        ViewCell::Flagged
+                    } else {
+                        ViewCell::Hidden
+                    },
+                );
+            }
+        }
+
+        Self {
+            width: board.width(),
+            height: board.height(),
+            num_mines: board.num_mines(),
+            cells,
+        }
+    }
+}
+
+/// The rule used to prove a cell safe or mined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A revealed number is satisfied by its flags, so its other hidden
+    /// neighbors are safe, or it has exactly as many hidden neighbors as
+    /// missing mines, so they are all mines
+    SingleCell,
+    /// The hidden neighbors of one number are a subset of another's, so the
+    /// cells only the larger one touches hold the difference in mines
+    Subset,
+    /// Every remaining mine is accounted for, so the other hidden cells are
+    /// safe, or there are as many hidden cells as remaining mines
+    MineCount,
+}
+
+/// The cells proven to be safe or mined by `deduce`, along with the `Rule`
+/// that proved each one
+#[derive(Debug, Clone, Default)]
+pub struct Deductions {
+    safe: HashMap<Point<u32>, Rule>,
+    mines: HashMap<Point<u32>, Rule>,
+}
+
+impl Deductions {
+    /// Returns the cells proven to be safe
+    pub fn safe(&self) -> &HashMap<Point<u32>, Rule> {
+        &self.safe
+    }
+
+    /// Returns the cells proven to be mines
+    pub fn mines(&self) -> &HashMap<Point<u32>, Rule> {
+        &self.mines
+    }
+
+    /// Returns true if nothing could be proven
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+
+    fn insert(&mut self, p: Point<u32>, is_mine: bool, rule: Rule) {
+        // keep the simplest rule that proved the cell
+        let map = if is_mine {
+            &mut self.mines
+        } else {
+            &mut self.safe
+        };
+        map.entry(p).or_insert(rule);
+    }
+
+    fn contains(&self, p: &Point<u32>) -> bool {
+        self.safe.contains_key(p) || self.mines.contains_key(p)
+    }
+}
+
+/// A constraint derived from a revealed number: exactly `mines` of the hidden
+/// `cells` contain a mine
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Constraint {
+    cells: Vec<usize>,
+    mines: usize,
+}
+
+impl Constraint {
+    fn is_subset_of(&self, other: &Self) -> bool {
+        self.cells.len() <= other.cells.len() && self.cells.iter().all(|c| other.cells.contains(c))
+    }
+
+    fn difference(&self, other: &Self) -> Vec<usize> {
+        self.cells
+            .iter()
+            .filter(|c| !other.cells.contains(c))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Returns every hidden cell that can be proven to be safe or a mine from the
+/// specified `View`, using the single cell, subset and global mine count rules
+/// in that order
+pub fn deduce(view: &View) -> Deductions {
+    let constraints = self::make_constraints(view);
+    let mut deductions = Deductions::default();
+
+    for c in &constraints {
+        if c.mines == 0 || c.mines == c.cells.len() {
+            for cell in &c.cells {
+                deductions.insert(view.point(*cell), c.mines > 0, Rule::SingleCell);
+            }
+        }
+    }
+
+    let mut by_cell: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for cell in &c.cells {
+            by_cell.entry(*cell).or_default().push(i);
+        }
+    }
+
+    for (i, a) in constraints.iter().enumerate() {
+        let overlapping = a
+            .cells
+            .iter()
+            .flat_map(|cell| &by_cell[cell])
+            .cloned()
+            .filter(|j| *j != i)
+            .collect::<HashSet<_>>();
+
+        for j in overlapping {
+            let b = &constraints[j];
+            if !a.is_subset_of(b) || a == b {
+                continue;
+            }
+
+            let diff = b.difference(a);
+            let diff_mines = match b.mines.checked_sub(a.mines) {
+                Some(m) => m,
+                None => continue,
+            };
+
+            if diff_mines == 0 || diff_mines == diff.len() {
+                for cell in diff {
+                    deductions.insert(view.point(cell), diff_mines > 0, Rule::Subset);
+                }
+            }
+        }
+    }
+
+    self::deduce_mine_count(view, &mut deductions);
+
+    deductions
+}
+
+fn deduce_mine_count(view: &View, deductions: &mut Deductions) {
+    let num_flags = view
+        .cells
+        .iter()
+        .filter(|c| **c == ViewCell::Flagged)
+        .count();
+
+    let remaining = match view
+        .num_mines
+        .checked_sub(num_flags + deductions.mines.len())
+    {
+        Some(r) => r,
+        None => return,
+    };
+
+    let unknown = (0..view.cells.len())
+        .filter(|i| view.cells[*i] == ViewCell::Hidden)
+        .map(|i| view.point(i))
+        .filter(|p| !deductions.contains(p))
+        .collect::<Vec<_>>();
+
+    if remaining == 0 || remaining == unknown.len() {
+        for p in unknown {
+            deductions.insert(p, remaining > 0, Rule::MineCount);
+        }
+    }
+}
+
+fn make_constraints(view: &View) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+
+    for (i, cell) in view.cells.iter().enumerate() {
+        let number = match cell {
+            ViewCell::Revealed(n) => *n as usize,
+            _ => continue,
+        };
+
+        let mut cells = Vec::new();
+        let mut num_flags = 0;
+        for n in view.neighbors(i) {
+            match view.cells[n] {
+                ViewCell::Hidden => cells.push(n),
+                ViewCell::Flagged => num_flags += 1,
+                ViewCell::Revealed(_) => {}
+            }
+        }
+
+        if cells.is_empty() {
+            continue;
+        }
+
+        cells.sort();
+
+        // a number with more flags than adjacent mines has been misflagged and
+        // cannot be reasoned about
+        if let Some(mines) = number.checked_sub(num_flags) {
+            constraints.push(Constraint { cells, mines });
+        }
+    }
+
+    constraints
+}
+
+/// A suggestion for the next cell to reveal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hint {
+    /// A cell proven to be safe by the specified `Rule`
+    Safe(Point<u32>, Rule),
+    /// No cell can be proven safe, so this is the cell least likely to contain
+    /// a mine, along with that probability
+    Guess(Point<u32>, f64),
+}
+
+impl Hint {
+    /// Returns the position of the suggested cell
+    pub fn pos(&self) -> Point<u32> {
+        match self {
+            Self::Safe(p, _) | Self::Guess(p, _) => *p,
+        }
+    }
+}
+
+/// Returns a `Hint` for the next cell to reveal in the specified `View`,
+/// preferring a cell that is proven to be safe over the lowest-risk guess. Ties
+/// are broken by picking the topmost, then leftmost cell. Returns `None` if
+/// there are no hidden cells.
+pub fn hint(view: &View) -> Option<Hint> {
+    let deductions = self::deduce(view);
+    let safe = deductions
+        .safe()
+        .iter()
+        .min_by_key(|(p, rule)| (**rule as u8, p.y, p.x))
+        .map(|(p, rule)| Hint::Safe(*p, *rule));
+
+    safe.or_else(|| {
+        self::probabilities(view)
+            .iter()
+            .filter(|(p, _)| !deductions.mines().contains_key(p))
+            .min_by(|(a, pa), (b, pb)| {
+                pa.partial_cmp(pb)
+                    .unwrap_or(Ordering::Equal)
+                    .then((a.y, a.x).cmp(&(b.y, b.x)))
+            })
+            .map(|(p, prob)| Hint::Guess(p, prob))
+    })
+}
+
+/// Returns true if every safe cell on the `board` can be revealed, starting
+/// with the cell at the specified `start` position, using deduction only. Any
+/// flags already on the `board` are ignored, and the `board` is not modified.
+pub fn is_solvable(board: &Board, start: Point<u32>) -> bool {
+    let mut board = board.clone();
+    board.clear_all(CellFlags::FLAG);
+
+    board.reveal_from(start.x, start.y);
+    if board.cell(start.x, start.y).contains(CellFlags::MINE) {
+        return false;
+    }
+
+    loop {
+        let deductions = self::deduce(&View::from(&board));
+        if deductions.is_empty() {
+            break;
+        }
+
+        for p in deductions.mines().keys() {
+            board.insert_flags(p.x, p.y, CellFlags::FLAG);
+        }
+
+        for p in deductions.safe().keys() {
+            board.reveal_from(p.x, p.y);
+        }
+    }
+
+    board.remaining_cells().len() == board.num_mines()
+}
@@ -0,0 +1,592 @@
+use super::{Constraint, View, ViewCell};
+use crate::math::Point;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Groups with more cells than this are never enumerated exactly
+const MAX_EXACT_CELLS: usize = 48;
+/// The maximum amount of search steps spent on a single group of cells before
+/// giving up
+const MAX_SEARCH_STEPS: usize = 500_000;
+/// The amount of resampling steps discarded before sampling starts
+const BURN_IN: usize = 2_000;
+/// The amount of resampling steps sampled
+const NUM_SAMPLES: usize = 20_000;
+/// The maximum amount of cells resampled in one step
+const WINDOW_SIZE: usize = 12;
+
+/// The probability of every hidden cell in a `View` containing a mine
+#[derive(Debug, Clone)]
+pub struct Probabilities {
+    width: usize,
+    values: Vec<Option<f64>>,
+    is_exact: bool,
+}
+
+impl Probabilities {
+    /// Returns `Some(f64)` with the probability of the cell at the specified `x`
+    /// and `y` position containing a mine, or `None` if the cell is revealed,
+    /// flagged or not present
+    pub fn get(&self, x: u32, y: u32) -> Option<f64> {
+        self.values
+            .get(y as usize * self.width + x as usize)
+            .cloned()
+            .flatten()
+    }
+
+    /// Returns an iterator over the position and probability of every hidden
+    /// cell
+    pub fn iter(&self) -> impl Iterator<Item = (Point<u32>, f64)> + '_ {
+        let width = self.width;
+        self.values.iter().enumerate().filter_map(move |(i, v)| {
+            v.map(|v| (point!((i % width) as u32, (i / width) as u32), v))
+        })
+    }
+
+    /// Returns true if the probabilities were calculated exactly, false if they
+    /// were approximated
+    pub fn is_exact(&self) -> bool {
+        self.is_exact
+    }
+}
+
+/// Calculates the probability of every hidden cell in the specified `View`
+/// containing a mine, given the revealed numbers, the flags and the total
+/// number of mines. Every layout that agrees with the `View` is considered
+/// equally likely.
+///
+/// The hidden cells next to revealed numbers are split into independent groups
+/// that are enumerated exactly. If a group is too large to enumerate, the
+/// probabilities are approximated by Monte-Carlo sampling instead.
+pub fn probabilities(view: &View) -> Probabilities {
+    self::exact(view).unwrap_or_else(|| self::monte_carlo(view, 0))
+}
+
+pub(super) fn exact(view: &View) -> Option<Probabilities> {
+    let constraints = super::make_constraints(view);
+    let (remaining, interior) = self::unconstrained(view, &constraints);
+
+    let mut groups = Vec::new();
+    for cells in self::split_groups(&constraints) {
+        if cells.len() > MAX_EXACT_CELLS {
+            return None;
+        }
+        groups.push(self::enumerate(cells, &constraints)?);
+    }
+
+    // the weight of placing `m` mines next to the numbers is the amount of ways
+    // to place the rest on the other hidden cells
+    let max_constrained = groups.iter().map(|g| g.solutions.len() - 1).sum::<usize>();
+    let weights = self::interior_weights(remaining, interior.len(), max_constrained);
+
+    let all = self::convolve_all(groups.iter().map(|g| &g.solutions));
+    let total = all
+        .iter()
+        .enumerate()
+        .map(|(m, n)| n * weights[m])
+        .sum::<f64>();
+
+    if total == 0.0 || !total.is_finite() {
+        // the view contradicts itself
+        return None;
+    }
+
+    let mut values = vec![None; view.cells.len()];
+
+    for (i, group) in groups.iter().enumerate() {
+        // the solutions of every other group combined
+        let others = self::convolve_all(
+            groups
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, g)| &g.solutions),
+        );
+
+        for (ci, cell) in group.cells.iter().enumerate() {
+            let mut p = 0.0;
+            for (k, counts) in group.mine_counts.iter().enumerate() {
+                for (m, n) in others.iter().enumerate() {
+                    p += counts[ci] * n * weights[k + m];
+                }
+            }
+            values[*cell] = Some(p / total);
+        }
+    }
+
+    if !interior.is_empty() {
+        let expected_mines = all
+            .iter()
+            .enumerate()
+            .map(|(m, n)| n * weights[m] * remaining.saturating_sub(m) as f64)
+            .sum::<f64>()
+            / total;
+
+        let p = expected_mines / interior.len() as f64;
+        for cell in interior {
+            values[cell] = Some(p);
+        }
+    }
+
+    Some(Probabilities {
+        width: view.width,
+        values,
+        is_exact: true,
+    })
+}
+
+/// Returns the amount of mines that are not flagged and the hidden cells that
+/// are not next to a revealed number
+fn unconstrained(view: &View, constraints: &[Constraint]) -> (usize, Vec<usize>) {
+    let mut is_constrained = vec![false; view.cells.len()];
+    for c in constraints {
+        for cell in &c.cells {
+            is_constrained[*cell] = true;
+        }
+    }
+
+    let num_flags = view
+        .cells
+        .iter()
+        .filter(|c| **c == ViewCell::Flagged)
+        .count();
+
+    let interior = (0..view.cells.len())
+        .filter(|i| view.cells[*i] == ViewCell::Hidden && !is_constrained[*i])
+        .collect();
+
+    (view.num_mines.saturating_sub(num_flags), interior)
+}
+
+/// Returns `weights[m]`, proportional to the amount of ways to place the
+/// `remaining` mines minus `m` on `interior` cells, for every `m` up to
+/// `max_m`
+fn interior_weights(remaining: usize, interior: usize, max_m: usize) -> Vec<f64> {
+    let mut log_weights = vec![None; max_m + 1];
+
+    // ln(interior choose r) for the largest r, then walk down using
+    // C(n, r - 1) = C(n, r) * r / (n - r + 1)
+    let first_m = remaining.saturating_sub(interior);
+    if first_m <= max_m {
+        let r = remaining - first_m;
+        let mut ln_choose = (0..r)
+            .map(|i| ((interior - i) as f64).ln() - ((i + 1) as f64).ln())
+            .sum::<f64>();
+
+        for (m, w) in log_weights.iter_mut().enumerate().skip(first_m) {
+            if m > remaining {
+                break;
+            }
+
+            *w = Some(ln_choose);
+
+            let r = remaining - m;
+            if r > 0 {
+                ln_choose += (r as f64).ln() - ((interior - r + 1) as f64).ln();
+            }
+        }
+    }
+
+    let max = log_weights
+        .iter()
+        .flatten()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    log_weights
+        .iter()
+        .map(|w| w.map(|w| (w - max).exp()).unwrap_or(0.0))
+        .collect()
+}
+
+fn convolve_all<'a>(counts: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    counts.fold(vec![1.0], |acc, c| {
+        let mut res = vec![0.0; acc.len() + c.len() - 1];
+        for (i, a) in acc.iter().enumerate() {
+            for (j, b) in c.iter().enumerate() {
+                res[i + j] += a * b;
+            }
+        }
+        res
+    })
+}
+
+/// Splits the cells of the specified `constraints` into groups that do not
+/// share a constraint, ordered so that neighboring cells are close together
+fn split_groups(constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    fn find(parent: &[usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            i = parent[i];
+        }
+        i
+    }
+
+    // constraints that share a cell are merged into the group of the first one
+    let mut parent = (0..constraints.len()).collect::<Vec<_>>();
+    let mut owner: HashMap<usize, usize> = HashMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for cell in &c.cells {
+            let j = *owner.entry(*cell).or_insert(i);
+            let (a, b) = (find(&parent, i), find(&parent, j));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut groups = vec![Vec::new(); constraints.len()];
+    let mut seen = HashSet::new();
+    for (i, c) in constraints.iter().enumerate() {
+        let root = find(&parent, i);
+        for cell in &c.cells {
+            if seen.insert(*cell) {
+                groups[root].push(*cell);
+            }
+        }
+    }
+
+    groups.into_iter().filter(|g| !g.is_empty()).collect()
+}
+
+/// The solutions of a group of cells, counted by the amount of mines they
+/// place
+struct Group {
+    cells: Vec<usize>,
+    /// `solutions[k]` is the amount of solutions with `k` mines
+    solutions: Vec<f64>,
+    /// `mine_counts[k][i]` is the amount of solutions with `k` mines that place
+    /// a mine on `cells[i]`
+    mine_counts: Vec<Vec<f64>>,
+}
+
+fn enumerate(cells: Vec<usize>, constraints: &[Constraint]) -> Option<Group> {
+    let mut search = Search::new(&cells, constraints, None);
+    let n = cells.len();
+    let mut group = Group {
+        cells,
+        solutions: vec![0.0; n + 1],
+        mine_counts: vec![vec![0.0; n]; n + 1],
+    };
+
+    let finished = search.run(0, &mut |mines| {
+        let k = mines.iter().filter(|m| **m).count();
+        group.solutions[k] += 1.0;
+        for (i, m) in mines.iter().enumerate() {
+            if *m {
+                group.mine_counts[k][i] += 1.0;
+            }
+        }
+        true
+    });
+
+    if finished {
+        Some(group)
+    } else {
+        None
+    }
+}
+
+/// A backtracking search over the mine assignments of a group of cells that
+/// satisfy the constraints touching them
+struct Search {
+    /// The constraints of each cell, by index into `mines_left`
+    cell_constraints: Vec<Vec<usize>>,
+    /// The mines each constraint still needs
+    mines_left: Vec<usize>,
+    /// The cells of each constraint that have not been assigned yet
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    steps: usize,
+    /// If set, the order in which values are tried is shuffled
+    rng: Option<ChaCha8Rng>,
+}
+
+impl Search {
+    /// Creates a new search over the specified `cells`. Cells outside of them
+    /// contain a mine if they do in `fixed`, or never if `fixed` is `None`.
+    fn new(cells: &[usize], constraints: &[Constraint], fixed: Option<&[bool]>) -> Self {
+        let index: HashMap<usize, usize> = cells.iter().enumerate().map(|(i, c)| (*c, i)).collect();
+
+        let mut cell_constraints = vec![Vec::new(); cells.len()];
+        let mut mines_left = Vec::new();
+        let mut unassigned = Vec::new();
+
+        for c in constraints {
+            if !c.cells.iter().any(|cell| index.contains_key(cell)) {
+                continue;
+            }
+
+            let mut num_inside = 0;
+            let mut num_fixed = 0;
+            for cell in &c.cells {
+                match index.get(cell) {
+                    Some(i) => {
+                        cell_constraints[*i].push(mines_left.len());
+                        num_inside += 1;
+                    }
+                    None if matches!(fixed, Some(f) if f[*cell]) => num_fixed += 1,
+                    None => {}
+                }
+            }
+            mines_left.push(c.mines.saturating_sub(num_fixed));
+            unassigned.push(num_inside);
+        }
+
+        Self {
+            cell_constraints,
+            mines_left,
+            unassigned,
+            assignment: vec![false; cells.len()],
+            steps: 0,
+            rng: None,
+        }
+    }
+
+    /// Calls `f` with every complete assignment until `f` returns false.
+    /// Returns false if the search gave up after `MAX_SEARCH_STEPS`.
+    fn run(&mut self, i: usize, f: &mut impl FnMut(&[bool]) -> bool) -> bool {
+        self.run_until(i, f).is_some()
+    }
+
+    /// Returns `None` if the search gave up, `Some(true)` if every assignment
+    /// was visited and `Some(false)` if `f` stopped the search
+    fn run_until(&mut self, i: usize, f: &mut impl FnMut(&[bool]) -> bool) -> Option<bool> {
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS {
+            return None;
+        }
+
+        if i == self.assignment.len() {
+            return Some(f(&self.assignment));
+        }
+
+        let mut values = [false, true];
+        if let Some(rng) = &mut self.rng {
+            values.shuffle(rng);
+        }
+
+        for is_mine in values.iter().cloned() {
+            let m = is_mine as usize;
+            let fits = self.cell_constraints[i]
+                .iter()
+                .all(|c| m <= self.mines_left[*c] && self.mines_left[*c] - m < self.unassigned[*c]);
+
+            if !fits {
+                continue;
+            }
+
+            for c in &self.cell_constraints[i] {
+                self.mines_left[*c] -= m;
+                self.unassigned[*c] -= 1;
+            }
+            self.assignment[i] = is_mine;
+
+            let res = self.run_until(i + 1, f);
+
+            for c in &self.cell_constraints[i] {
+                self.mines_left[*c] += m;
+                self.unassigned[*c] += 1;
+            }
+
+            match res {
+                Some(true) => {}
+                other => return other,
+            }
+        }
+
+        Some(true)
+    }
+}
+
+/// Approximates the probabilities by sampling layouts that agree with the
+/// `View`. Only the cells next to revealed numbers are sampled, a few at a time
+/// along the numbers they share, while the other hidden cells are accounted for
+/// by counting the ways to place the rest of the mines on them.
+pub(super) fn monte_carlo(view: &View, seed: u64) -> Probabilities {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let constraints = super::make_constraints(view);
+    let (remaining, interior) = self::unconstrained(view, &constraints);
+    let frontier = self::split_groups(&constraints)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut is_mine = match self::find_layout(
+        view,
+        &constraints,
+        &frontier,
+        remaining,
+        interior.len(),
+        &mut rng,
+    ) {
+        Some(l) => l,
+        None => return self::uniform(view, remaining, interior.len() + frontier.len()),
+    };
+
+    let mut cell_constraints = vec![Vec::new(); view.cells.len()];
+    for (i, c) in constraints.iter().enumerate() {
+        for cell in &c.cells {
+            cell_constraints[*cell].push(i);
+        }
+    }
+
+    let weights = self::interior_weights(remaining, interior.len(), frontier.len());
+    let mut num_mines = frontier.iter().filter(|c| is_mine[**c]).count();
+
+    // time each cell has spent as a mine, updated whenever the cell changes
+    let mut mine_time = vec![0usize; view.cells.len()];
+    let mut changed_at = vec![0usize; view.cells.len()];
+    let mut interior_mine_time = 0;
+
+    for step in 0..BURN_IN + NUM_SAMPLES {
+        let t = step.saturating_sub(BURN_IN);
+
+        let window = self::sample_window(&frontier, &constraints, &cell_constraints, &mut rng);
+        let num_outside = num_mines - window.iter().filter(|c| is_mine[**c]).count();
+
+        let mut options = Vec::new();
+        let mut search = Search::new(&window, &constraints, Some(&is_mine));
+        let finished = search.run(0, &mut |mines| {
+            let k = mines.iter().filter(|m| **m).count();
+            if let Some(w) = weights.get(num_outside + k).filter(|w| **w > 0.0) {
+                options.push((mines.to_vec(), *w));
+            }
+            true
+        });
+
+        let choice = if finished {
+            options.choose_weighted(&mut rng, |o| o.1).ok()
+        } else {
+            None
+        };
+
+        if let Some((mines, _)) = choice {
+            for (cell, new) in window.iter().zip(mines) {
+                if *new != is_mine[*cell] {
+                    if is_mine[*cell] {
+                        mine_time[*cell] += t - changed_at[*cell];
+                        num_mines -= 1;
+                    } else {
+                        num_mines += 1;
+                    }
+                    changed_at[*cell] = t;
+                    is_mine[*cell] = *new;
+                }
+            }
+        }
+
+        if step >= BURN_IN {
+            interior_mine_time += remaining - num_mines;
+        }
+    }
+
+    let mut values = vec![None; view.cells.len()];
+    for cell in frontier {
+        if is_mine[cell] {
+            mine_time[cell] += NUM_SAMPLES - changed_at[cell];
+        }
+        values[cell] = Some(mine_time[cell] as f64 / NUM_SAMPLES as f64);
+    }
+
+    if !interior.is_empty() {
+        let p = interior_mine_time as f64 / (NUM_SAMPLES * interior.len()) as f64;
+        for cell in interior {
+            values[cell] = Some(p);
+        }
+    }
+
+    Probabilities {
+        width: view.width,
+        values,
+        is_exact: false,
+    }
+}
+
+/// Returns every hidden cell the same probability, used when no layout agreeing
+/// with the `View` could be found
+fn uniform(view: &View, remaining: usize, num_hidden: usize) -> Probabilities {
+    let p = (remaining as f64 / num_hidden.max(1) as f64).min(1.0);
+    let values = view
+        .cells
+        .iter()
+        .map(|c| {
+            if *c == ViewCell::Hidden {
+                Some(p)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    Probabilities {
+        width: view.width,
+        values,
+        is_exact: false,
+    }
+}
+
+/// Returns a random placement of mines on the `frontier` cells that agrees
+/// with the `View` and leaves a number of mines that fits on the other hidden
+/// cells, if one can be found
+fn find_layout(
+    view: &View,
+    constraints: &[Constraint],
+    frontier: &[usize],
+    remaining: usize,
+    num_interior: usize,
+    rng: &mut ChaCha8Rng,
+) -> Option<Vec<bool>> {
+    let mut search = Search::new(frontier, constraints, None);
+    search.rng = Some(ChaCha8Rng::seed_from_u64(rng.gen()));
+
+    let mut found = None;
+    search.run(0, &mut |mines| {
+        let k = mines.iter().filter(|m| **m).count();
+        if k <= remaining && remaining - k <= num_interior {
+            found = Some(mines.to_vec());
+            false
+        } else {
+            true
+        }
+    });
+
+    let mut is_mine = vec![false; view.cells.len()];
+    for (cell, m) in frontier.iter().zip(found?) {
+        is_mine[*cell] = m;
+    }
+
+    Some(is_mine)
+}
+
+/// Returns up to `WINDOW_SIZE` cells of the `frontier`, starting from a random
+/// cell and following the constraints it shares with its neighbors
+fn sample_window(
+    frontier: &[usize],
+    constraints: &[Constraint],
+    cell_constraints: &[Vec<usize>],
+    rng: &mut ChaCha8Rng,
+) -> Vec<usize> {
+    let start = match frontier.choose(rng) {
+        Some(c) => *c,
+        None => return Vec::new(),
+    };
+
+    let mut window = vec![start];
+    let mut i = 0;
+    while i < window.len() && window.len() < WINDOW_SIZE {
+        let mut next = cell_constraints[window[i]]
+            .iter()
+            .flat_map(|c| constraints[*c].cells.iter().cloned())
+            .filter(|c| !window.contains(c))
+            .collect::<Vec<_>>();
+        next.sort();
+        next.dedup();
+        next.shuffle(rng);
+
+        let free = WINDOW_SIZE - window.len();
+        window.extend(next.into_iter().take(free));
+        i += 1;
+    }
+
+    window
+}
@@ -0,0 +1,363 @@
+use super::*;
+use crate::config::difficulties;
+use itertools::Itertools;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Creates a `View` from rows of `.` (hidden), `F` (flagged) and digits
+/// (revealed)
+fn make_view(num_mines: usize, rows: &[&str]) -> View {
+    let cells = rows
+        .iter()
+        .flat_map(|r| r.chars())
+        .map(|c| match c {
+            '.' => ViewCell::Hidden,
+            'F' => ViewCell::Flagged,
+            _ => ViewCell::Revealed(c.to_digit(10).unwrap() as u8),
+        })
+        .collect();
+    View::new(rows[0].len(), rows.len(), num_mines, cells).unwrap()
+}
+
+fn make_board(width: usize, height: usize, mines: &[Point<u32>]) -> Board {
+    let mut b = Board::new(width, height, mines.len()).unwrap();
+    b.clear_all(CellFlags::MINE);
+    for p in mines {
+        b.set_mine(p.x, p.y, true);
+    }
+    b
+}
+
+fn points(rule: Rule, ps: &[(u32, u32)]) -> HashMap<Point<u32>, Rule> {
+    ps.iter().map(|p| (Point::from(*p), rule)).collect()
+}
+
+fn assert_close(expected: f64, actual: Option<f64>, tolerance: f64) {
+    let actual = actual.unwrap();
+    assert!(
+        (expected - actual).abs() <= tolerance,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn test_view_new() {
+    assert!(View::new(2, 2, 1, vec![ViewCell::Hidden; 4]).is_ok());
+    assert!(View::new(2, 2, 1, vec![ViewCell::Hidden; 3]).is_err());
+}
+
+#[test]
+fn test_view_from_board() {
+    let mut b = make_board(3, 1, &[point!(0, 0)]);
+    b.insert_flags(1, 0, CellFlags::REVEALED);
+    b.insert_flags(2, 0, CellFlags::FLAG);
+    let v = View::from(&b);
+    assert_eq!(ViewCell::Hidden, v.cell(0, 0));
+    assert_eq!(ViewCell::Revealed(1), v.cell(1, 0));
+    assert_eq!(ViewCell::Flagged, v.cell(2, 0));
+    assert_eq!(1, v.num_mines());
+}
+
+#[test]
+fn test_view_hides_mines() {
+    // the same visible state with the mine in either corner
+    let mut b1 = make_board(3, 2, &[point!(0, 0)]);
+    let mut b2 = make_board(3, 2, &[point!(0, 1)]);
+    for b in [&mut b1, &mut b2].iter_mut() {
+        b.reveal_from(2, 0);
+    }
+    let (d1, d2) = (
+        super::deduce(&View::from(&b1)),
+        super::deduce(&View::from(&b2)),
+    );
+    assert!(d1.is_empty());
+    assert!(d2.is_empty());
+}
+
+#[test]
+fn test_deduce_nothing() {
+    assert!(super::deduce(&make_view(1, &["...", "...", "..."])).is_empty());
+    assert!(super::deduce(&make_view(3, &["...", "121", "..."]))
+        .safe()
+        .is_empty());
+}
+
+#[test]
+fn test_deduce_single_cell_safe() {
+    let d = super::deduce(&make_view(1, &["1F.", "11.", "..."]));
+    assert_eq!(
+        &points(Rule::SingleCell, &[(2, 0), (2, 1), (0, 2), (1, 2), (2, 2)]),
+        d.safe()
+    );
+    assert!(d.mines().is_empty());
+}
+
+#[test]
+fn test_deduce_single_cell_mines() {
+    let d = super::deduce(&make_view(1, &[".1", "11"]));
+    assert_eq!(&points(Rule::SingleCell, &[(0, 0)]), d.mines());
+    assert!(d.safe().is_empty());
+
+    let d = super::deduce(&make_view(3, &["..", "3."]));
+    assert_eq!(
+        &points(Rule::SingleCell, &[(0, 0), (1, 0), (1, 1)]),
+        d.mines()
+    );
+}
+
+#[test]
+fn test_deduce_subset() {
+    // 1-2-1
+    let d = super::deduce(&make_view(5, &["...", "121"]));
+    assert_eq!(&points(Rule::Subset, &[(0, 0), (2, 0)]), d.mines());
+    assert!(d.safe().is_empty());
+
+    // 1-1 against a wall
+    let d = super::deduce(&make_view(5, &["...", "11."]));
+    assert_eq!(&points(Rule::Subset, &[(2, 0), (2, 1)]), d.safe());
+    assert!(d.mines().is_empty());
+}
+
+#[test]
+fn test_deduce_mine_count() {
+    let d = super::deduce(&make_view(2, &["FF", ".."]));
+    assert_eq!(&points(Rule::MineCount, &[(0, 1), (1, 1)]), d.safe());
+
+    let d = super::deduce(&make_view(4, &["FF", ".."]));
+    assert_eq!(&points(Rule::MineCount, &[(0, 1), (1, 1)]), d.mines());
+
+    // the mines found by the other rules count towards the total
+    let d = super::deduce(&make_view(2, &["...", "121"]));
+    assert_eq!(&points(Rule::Subset, &[(0, 0), (2, 0)]), d.mines());
+    assert_eq!(&points(Rule::MineCount, &[(1, 0)]), d.safe());
+}
+
+#[test]
+fn test_deduce_misflagged() {
+    let d = super::deduce(&make_view(5, &["1F", "F."]));
+    assert!(d.is_empty());
+}
+
+#[test]
+fn test_deduce_steps() {
+    let mut v = make_view(5, &["...", "121"]);
+    let d = super::deduce(&v);
+    assert!(d.safe().is_empty());
+
+    v.apply(&d);
+    assert_eq!(ViewCell::Flagged, v.cell(0, 0));
+
+    let d = super::deduce(&v);
+    assert_eq!(&points(Rule::SingleCell, &[(1, 0)]), d.safe());
+    assert!(d.mines().is_empty());
+}
+
+#[test]
+fn test_deduce_is_sound() {
+    // compares every deduction against all the layouts that agree with what the
+    // player can see
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    for seed in 0..200 {
+        let mut b = Board::with_seed(4, 4, rng.gen_range(1, 6), seed).unwrap();
+        for _ in 0..rng.gen_range(1, 4) {
+            let (x, y) = (rng.gen_range(0, 4), rng.gen_range(0, 4));
+            if !b.cell(x, y).contains(CellFlags::MINE) {
+                b.reveal_from(x, y);
+            }
+        }
+
+        let v = View::from(&b);
+        let d = super::deduce(&v);
+        let hidden = (0..16)
+            .filter(|i| v.cells[*i] == ViewCell::Hidden)
+            .collect::<Vec<_>>();
+
+        for mines in hidden.iter().combinations(v.num_mines()) {
+            if !is_consistent(&v, &mines) {
+                continue;
+            }
+
+            for (p, rule) in d.mines() {
+                assert!(mines.contains(&&v.index(p.x, p.y)), "{:?} {:?}", p, rule);
+            }
+
+            for (p, rule) in d.safe() {
+                assert!(!mines.contains(&&v.index(p.x, p.y)), "{:?} {:?}", p, rule);
+            }
+        }
+    }
+}
+
+fn is_consistent(v: &View, mines: &[&usize]) -> bool {
+    v.cells.iter().enumerate().all(|(i, c)| match c {
+        ViewCell::Revealed(n) => {
+            v.neighbors(i).filter(|j| mines.contains(&j)).count() == *n as usize
+        }
+        _ => true,
+    })
+}
+
+#[test]
+fn test_is_solvable() {
+    // an empty board opens completely from any cell
+    let b = Board::new(9, 9, 0).unwrap();
+    assert!(super::is_solvable(&b, point!(4, 4)));
+
+    // starting on a mine is never solvable
+    let b = make_board(9, 9, &[point!(4, 4)]);
+    assert!(!super::is_solvable(&b, point!(4, 4)));
+    assert!(super::is_solvable(&b, point!(0, 0)));
+
+    // two cells in the corner that can only be told apart by guessing
+    let b = make_board(3, 2, &[point!(0, 0)]);
+    assert!(!super::is_solvable(&b, point!(2, 1)));
+}
+
+#[test]
+fn test_is_solvable_ignores_flags() {
+    let mut b = Board::new(9, 9, 0).unwrap();
+    b.insert_flags(0, 0, CellFlags::FLAG);
+    assert!(super::is_solvable(&b, point!(4, 4)));
+    assert!(b.cell(0, 0).contains(CellFlags::FLAG));
+    assert!(!b.cell(4, 4).contains(CellFlags::REVEALED));
+}
+
+#[test]
+fn test_no_guess_difficulties() {
+    for difficulty in difficulties::ALL {
+        let d = difficulties::get(difficulty).unwrap();
+        for seed in 0..3 {
+            let mut b =
+                Board::new_deferred(d.dimen.width(), d.dimen.height(), d.num_mines, seed).unwrap();
+            assert!(b.generate(3, 3, true, true));
+            assert!(super::is_solvable(&b, point!(3, 3)));
+        }
+    }
+}
+
+#[test]
+fn test_hint_safe() {
+    let v = make_view(2, &["1F.", "..."]);
+    assert_eq!(
+        Some(Hint::Safe(point!(0, 1), Rule::SingleCell)),
+        self::hint(&v)
+    );
+}
+
+#[test]
+fn test_hint_guess() {
+    // every hidden cell is equally likely to be a mine
+    let v = make_view(2, &["..1.."]);
+    assert_eq!(Some(Hint::Guess(point!(0, 0), 0.5)), self::hint(&v));
+
+    // one mine is among the eight cells next to the 1 and the other among the
+    // three cells on the right
+    let v = make_view(2, &["....", ".1..", "...."]);
+    match self::hint(&v) {
+        Some(Hint::Guess(p, prob)) => {
+            assert_eq!(point!(0, 0), p);
+            assert!((prob - 0.125).abs() < 1e-9);
+        }
+        h => panic!("unexpected hint {:?}", h),
+    }
+}
+
+#[test]
+fn test_hint_none() {
+    assert_eq!(None, self::hint(&make_view(0, &["00", "00"])));
+}
+
+#[test]
+fn test_probabilities_certain() {
+    let p = super::probabilities(&make_view(2, &["...", "121"]));
+    assert!(p.is_exact());
+    assert_close(1.0, p.get(0, 0), 1e-9);
+    assert_close(0.0, p.get(1, 0), 1e-9);
+    assert_close(1.0, p.get(2, 0), 1e-9);
+    assert_eq!(None, p.get(0, 1));
+    assert_eq!(3, p.iter().count());
+}
+
+#[test]
+fn test_probabilities_interior() {
+    // the only mine left is one of the eight cells next to the 1
+    let p = super::probabilities(&make_view(2, &[".....", "F.1..", "....."]));
+    assert_eq!(None, p.get(0, 1));
+    assert_close(1.0 / 8.0, p.get(1, 1), 1e-9);
+    assert_close(0.0, p.get(0, 0), 1e-9);
+
+    let p = super::probabilities(&make_view(2, &["..1.."]));
+    assert_close(0.5, p.get(1, 0), 1e-9);
+    assert_close(0.5, p.get(3, 0), 1e-9);
+    assert_close(0.5, p.get(0, 0), 1e-9);
+    assert_close(0.5, p.get(4, 0), 1e-9);
+
+    let p = super::probabilities(&make_view(1, &["..1.."]));
+    assert_close(0.5, p.get(1, 0), 1e-9);
+    assert_close(0.0, p.get(0, 0), 1e-9);
+}
+
+#[test]
+fn test_probabilities_no_numbers() {
+    let p = super::probabilities(&make_view(3, &["...", "...", "..."]));
+    for (_, v) in p.iter() {
+        assert_close(1.0 / 3.0, Some(v), 1e-9);
+    }
+}
+
+/// Counts the layouts that agree with what the player can see on random boards
+/// and compares the share of them that place a mine on each cell
+#[test]
+fn test_probabilities_exact() {
+    for seed in 0..100 {
+        let mut b = Board::with_seed(4, 4, 1 + seed as usize % 5, seed).unwrap();
+        let start = (0..16)
+            .map(|i| (i % 4, i / 4))
+            .find(|(x, y)| !b.cell(*x, *y).contains(CellFlags::MINE))
+            .unwrap();
+        b.reveal_from(start.0, start.1);
+
+        let v = View::from(&b);
+        let p = super::probabilities(&v);
+        let hidden = (0..16)
+            .filter(|i| v.cells[*i] == ViewCell::Hidden)
+            .collect::<Vec<_>>();
+
+        let layouts = hidden
+            .iter()
+            .combinations(v.num_mines())
+            .filter(|mines| self::is_consistent(&v, mines))
+            .collect::<Vec<_>>();
+
+        for cell in &hidden {
+            let n = layouts.iter().filter(|l| l.contains(&cell)).count();
+            let expected = n as f64 / layouts.len() as f64;
+            assert_close(expected, p.get((cell % 4) as u32, (cell / 4) as u32), 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_monte_carlo() {
+    for seed in 0..5 {
+        let mut b = Board::with_seed(8, 8, 10, seed).unwrap();
+        let start = (0..64)
+            .map(|i| (i % 8, i / 8))
+            .find(|(x, y)| {
+                b.count_adjacent_mines(*x, *y) == 0 && !b.cell(*x, *y).contains(CellFlags::MINE)
+            })
+            .unwrap();
+        b.reveal_from(start.0, start.1);
+
+        let v = View::from(&b);
+        let exact = probability::exact(&v).unwrap();
+        let approx = probability::monte_carlo(&v, seed);
+        assert!(!approx.is_exact());
+
+        for (p, e) in exact.iter() {
+            assert_close(e, approx.get(p.x, p.y), 0.1);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::data;
+use crate::record::GameRecord;
+use crate::scores;
+use crate::MsResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Lifetime statistics of the games played on a single board
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BoardStats {
+    pub played: u32,
+    pub won: u32,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// The time spent on won games
+    pub win_time: Duration,
+    /// The time spent on all games
+    pub total_time: Duration,
+    /// The 3BV of the boards of won games that were not assisted, see
+    /// `Board::bbbv` and `GameRecord::is_assisted`
+    #[serde(default)]
+    pub bbbv: usize,
+    /// The time spent on the won games counted in `bbbv`
+    #[serde(default)]
+    pub bbbv_time: Duration,
+    /// The clicks made in the won games counted in `bbbv`
+    #[serde(default)]
+    pub clicks: usize,
+    /// The most 3BV per second cleared in a won game counted in `bbbv`
+    #[serde(default)]
+    pub best_bbbv_per_sec: f64,
+}
+
+impl BoardStats {
+    /// Counts the game of the specified `record`
+    pub fn add(&mut self, record: &GameRecord) {
+        self.played += 1;
+        self.total_time += record.elapsed;
+
+        if record.did_win {
+            self.won += 1;
+            self.win_time += record.elapsed;
+            self.current_streak += 1;
+            self.longest_streak = self.longest_streak.max(self.current_streak);
+
+            // help from hints, undo or the heatmap skews the clicks and time
+            if !record.is_assisted() {
+                self.bbbv += record.bbbv;
+                self.bbbv_time += record.elapsed;
+                self.clicks += record.clicks.total();
+                self.best_bbbv_per_sec = self.best_bbbv_per_sec.max(record.bbbv_per_sec());
+            }
+        } else {
+            self.current_streak = 0;
+        }
+    }
+
+    /// Returns the fraction of games played that were won, from 0 to 1
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.won as f64 / self.played as f64
+        }
+    }
+
+    /// Returns `Some(Duration)` with the average time of a won game, or `None`
+    /// if no game has been won yet
+    pub fn average_win_time(&self) -> Option<Duration> {
+        if self.won == 0 {
+            None
+        } else {
+            Some(self.win_time / self.won)
+        }
+    }
+
+    /// Returns `Some(f64)` with the 3BV per second cleared over all won games,
+    /// or `None` if no game has been won yet
+    pub fn bbbv_per_sec(&self) -> Option<f64> {
+        match self.bbbv_time.as_secs_f64() {
+            secs if secs > 0.0 => Some(self.bbbv as f64 / secs),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some(f64)` with the 3BV per click made over all won games, like
+    /// `GameRecord::efficiency`, or `None` if no game has been won yet
+    pub fn efficiency(&self) -> Option<f64> {
+        match self.clicks {
+            0 => None,
+            n => Some(self.bbbv as f64 / n as f64),
+        }
+    }
+
+    /// Returns the statistics as a JSON object, with times in seconds
+    pub fn to_json(&self) -> Value {
+        json!({
+            "played": self.played,
+            "won": self.won,
+            "win_rate": self.win_rate(),
+            "current_streak": self.current_streak,
+            "longest_streak": self.longest_streak,
+            "average_win_time": self.average_win_time().map(|d| d.as_secs_f64()),
+            "total_time": self.total_time.as_secs_f64(),
+            "bbbv_per_sec": self.bbbv_per_sec(),
+            "best_bbbv_per_sec": self.best_bbbv_per_sec,
+            "efficiency": self.efficiency(),
+        })
+    }
+}
+
+/// The `BoardStats` of every board played on, named like the tables of
+/// `scores::HighScores`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    boards: BTreeMap<String, BoardStats>,
+}
+
+impl Stats {
+    /// Returns `Some(&BoardStats)` of the board with the specified `name`, or
+    /// `None` if no game has been played on it
+    pub fn get(&self, name: &str) -> Option<&BoardStats> {
+        self.boards.get(name)
+    }
+
+    /// Counts the game of the specified `record` toward the statistics of its
+    /// board
+    pub fn add(&mut self, record: &GameRecord) {
+        self.boards
+            .entry(scores::record_board_name(record))
+            .or_default()
+            .add(record)
+    }
+
+    /// Returns the statistics of every board as a JSON object keyed by board
+    pub fn to_json(&self) -> Value {
+        Value::Object(
+            self.boards
+                .iter()
+                .map(|(name, s)| (name.clone(), s.to_json()))
+                .collect(),
+        )
+    }
+}
+
+/// Returns the path of the statistics file, see `data::path`
+pub fn path() -> MsResult<PathBuf> {
+    data::path("stats.json")
+}
+
+/// Writes the specified `stats` to the file at `path`
+pub fn write(stats: &Stats, path: &Path) -> MsResult {
+    data::write(stats, path, true)
+}
+
+/// Reads the `Stats` in the file at `path`. Returns empty `Stats` if there is
+/// no such file or an `Err(String)` if the file could not be read.
+pub fn read(path: &Path) -> MsResult<Stats> {
+    data::read(path, "statistics file").map(Option::unwrap_or_default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use crate::data;
+    use crate::record::{Clicks, GameRecord};
+    use std::time::Duration;
+
+    #[test]
+    fn test_streaks() {
+        let mut stats = Stats::default();
+        assert!(stats.get("beginner").is_none());
+
+        for (did_win, secs) in &[(true, 10), (true, 20), (false, 5), (true, 30)] {
+            let record = GameRecord::new_test(*did_win, 9, 9, 10, Duration::from_secs(*secs));
+            stats.add(&record);
+        }
+
+        let s = stats.get("beginner").unwrap();
+        assert_eq!(4, s.played);
+        assert_eq!(3, s.won);
+        assert_eq!(1, s.current_streak);
+        assert_eq!(2, s.longest_streak);
+        assert_eq!(0.75, s.win_rate());
+        assert_eq!(Some(Duration::from_secs(20)), s.average_win_time());
+        assert_eq!(Duration::from_secs(65), s.total_time);
+    }
+
+    #[test]
+    fn test_stats_json() {
+        let mut stats = Stats::default();
+        let record = GameRecord::new_test(false, 9, 9, 10, Duration::from_secs(3));
+        stats.add(&record);
+
+        let json = stats.to_json();
+        assert_eq!(1, json["beginner"]["played"]);
+        assert_eq!(0.0, json["beginner"]["win_rate"]);
+        assert!(json["beginner"]["average_win_time"].is_null());
+        assert_eq!(3.0, json["beginner"]["total_time"]);
+
+        let loaded = data::round_trip("stats", &stats, super::write, super::read).unwrap();
+        assert_eq!(stats, loaded);
+    }
+
+    #[test]
+    fn test_bbbv() {
+        let mut stats = Stats::default();
+        for (secs, bbbv, left, lost, undo) in &[
+            (10, 20, 25, false, false),
+            (5, 20, 15, false, false),
+            (4, 30, 4, true, false),
+            (2, 30, 40, false, true),
+        ] {
+            let mut record = GameRecord::new_test(!lost, 9, 9, 10, Duration::from_secs(*secs));
+            record.bbbv = *bbbv;
+            record.undo_used = *undo;
+            record.clicks = Clicks {
+                left: *left,
+                right: 5,
+                chord: 0,
+            };
+            stats.add(&record);
+        }
+
+        // lost and assisted games do not count
+        let s = stats.get("beginner").unwrap();
+        assert_eq!(3, s.won);
+        assert_eq!(Some(40.0 / 15.0), s.bbbv_per_sec());
+        assert_eq!(4.0, s.best_bbbv_per_sec);
+        assert_eq!(Some(40.0 / 50.0), s.efficiency());
+        assert_eq!(4.0, stats.to_json()["beginner"]["best_bbbv_per_sec"]);
+
+        // statistics from before 3BV was tracked
+        let json = r#"{"boards":{"beginner":{"played":1,"won":1,"current_streak":1,
+            "longest_streak":1,"win_time":{"secs":9,"nanos":0},
+            "total_time":{"secs":9,"nanos":0}}}}"#;
+        let old = serde_json::from_str::<Stats>(json).unwrap();
+        assert_eq!(None, old.get("beginner").unwrap().bbbv_per_sec());
+        assert_eq!(None, old.get("beginner").unwrap().efficiency());
+    }
+}